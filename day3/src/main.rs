@@ -1,16 +1,105 @@
-use shared::get_lines_from_file;
+mod selection;
+use selection::{
+    MAX_VALUE_DIGITS, Objective, SelectionRule, indices_to_value, select, select_candidates, select_largest,
+};
+use shared::{Lcg, get_lines_from_file};
 use std::{error::Error, path::Path};
 type Int = i64;
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Algorithm {
+    // Single pass, checks up to 12 slots per digit and zeroes the tail on a replacement: O(n*k)
+    SlotScan,
+    // Monotonic stack ("remove n-k digits"), each digit is pushed and popped at most once: O(n)
+    MonotonicStack,
+}
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Validation {
+    // Any short line, non-digit character or read error is reported as an error
+    Strict,
+    // Invalid lines are skipped (with a warning), reading stops at the first read error (also with a warning)
+    Lenient,
+}
 fn main() {
+    let mut algorithm = Algorithm::SlotScan;
+    let mut validation = Validation::Strict;
+    let mut explain = false;
+    let mut num_candidates = None;
+    let mut rule = SelectionRule::new(Objective::Maximise, 12);
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--stack" => algorithm = Algorithm::MonotonicStack,
+            "--slots" => algorithm = Algorithm::SlotScan,
+            "--lenient" => validation = Validation::Lenient,
+            "--explain" => explain = true,
+            // Options for listing the best candidates for each line under a different selection rule. Values are
+            // only taken if the next argument isn't another flag.
+            "--candidates" => {
+                num_candidates = args
+                    .next_if(|x| !x.starts_with("--"))
+                    .and_then(|x| x.parse().ok())
+                    .or(Some(1))
+            }
+            "--count" => {
+                rule.count = args
+                    .next_if(|x| !x.starts_with("--"))
+                    .and_then(|x| x.parse().ok())
+                    .unwrap_or(rule.count)
+            }
+            "--minimise" => rule.objective = Objective::Minimise,
+            "--no-adjacent" => rule.constraints.no_adjacent = true,
+            "--forbid" => {
+                let forbidden = args.next().unwrap_or_default();
+                rule.constraints.forbidden_digits =
+                    forbidden.bytes().filter(u8::is_ascii_digit).map(|x| x - b'0').collect();
+            }
+            "--bench" => {
+                // Optional line length, defaults to 10^6 digits. Only taken if the next argument isn't another flag.
+                let line_length = args
+                    .next_if(|x| !x.starts_with("--"))
+                    .and_then(|x| x.parse().ok())
+                    .unwrap_or(1_000_000);
+                run_benchmark(line_length);
+                return;
+            }
+            _ => {
+                println!("Unknown argument: {}", arg);
+                return;
+            }
+        }
+    }
+    if rule.count > MAX_VALUE_DIGITS {
+        println!(
+            "--count can be at most {}, larger values don't fit in an integer",
+            MAX_VALUE_DIGITS
+        );
+        return;
+    }
     let start = std::time::Instant::now();
-    let line_iterator = match read_input("input") {
+    let line_iterator = match read_input("input", validation) {
         Err(error) => {
-            println!("Error occured reading day 3 input: {}", error.to_string());
+            println!("Error occured reading day 3 input: {}", error);
             return;
         }
         Ok(iterator) => iterator,
     };
-    let (part1, part2) = calculate_answers(line_iterator);
+    if let Some(num_candidates) = num_candidates {
+        for (line_idx, line) in line_iterator.enumerate() {
+            let candidates = select_candidates(line.as_bytes(), &rule, num_candidates);
+            println!("Line {}: {} candidates", line_idx + 1, candidates.len());
+            for indices in candidates {
+                println!("  {} at {:?}", indices_to_value(line.as_bytes(), &indices), indices);
+            }
+        }
+        return;
+    }
+    if explain {
+        get_line_selections(line_iterator)
+            .iter()
+            .for_each(|selection| println!("{}", selection.render()));
+        return;
+    }
+    let (part1, part2) = calculate_answers(line_iterator, algorithm);
     let elapsed = start.elapsed();
     println!(
         "\tDay 3\nPart 1: {}\nPart 2: {}\nTime:   {}",
@@ -19,11 +108,14 @@ fn main() {
         elapsed.as_micros()
     );
 }
-fn calculate_answers(line_iterator: Box<dyn Iterator<Item = String>>) -> (Int, Int) {
+fn calculate_answers(line_iterator: Box<dyn Iterator<Item = String>>, algorithm: Algorithm) -> (Int, Int) {
+    let line_result = match algorithm {
+        Algorithm::SlotScan => get_line_result,
+        Algorithm::MonotonicStack => get_line_result_stack,
+    };
     line_iterator
-        .map(get_line_result)
-        .reduce(|lhs, rhs| (lhs.0 + rhs.0, lhs.1 + rhs.1))
-        .unwrap()
+        .map(line_result)
+        .fold((0, 0), |lhs, rhs| (lhs.0 + rhs.0, lhs.1 + rhs.1))
 }
 
 fn get_line_result(line: String) -> (Int, Int) {
@@ -61,13 +153,142 @@ fn get_line_result(line: String) -> (Int, Int) {
     )
 }
 
-fn read_input<P>(filename: P) -> Result<Box<dyn Iterator<Item = String>>, Box<dyn Error>>
+fn get_line_result_stack(line: String) -> (Int, Int) {
+    let digits = line.as_bytes();
+    (largest_subsequence(digits, 2), largest_subsequence(digits, 12))
+}
+
+fn largest_subsequence(digits: &[u8], count: usize) -> Int { indices_to_value(digits, &select_largest(digits, count)) }
+
+struct LineSelection {
+    line: String,
+    part_one_indices: Vec<usize>,
+    part_two_indices: Vec<usize>,
+}
+impl LineSelection {
+    fn new(line: String) -> LineSelection {
+        // Lines too short for a part have nothing selected for it
+        let part_one_indices = select(line.as_bytes(), &SelectionRule::new(Objective::Maximise, 2)).unwrap_or_default();
+        let part_two_indices =
+            select(line.as_bytes(), &SelectionRule::new(Objective::Maximise, 12)).unwrap_or_default();
+        LineSelection {
+            line,
+            part_one_indices,
+            part_two_indices,
+        }
+    }
+    fn values(&self) -> (Int, Int) {
+        (
+            indices_to_value(self.line.as_bytes(), &self.part_one_indices),
+            indices_to_value(self.line.as_bytes(), &self.part_two_indices),
+        )
+    }
+    fn render(&self) -> String {
+        // The line followed by a marker line for each part, with a '^' under each selected digit
+        let marker_line = |indices: &[usize]| {
+            let mut markers = vec![b' '; self.line.len()];
+            indices.iter().for_each(|&idx| markers[idx] = b'^');
+            String::from_utf8(markers).unwrap()
+        };
+        let (part1, part2) = self.values();
+        format!(
+            "{}\n{}  Part 1: {} at {:?}\n{}  Part 2: {} at {:?}",
+            self.line,
+            marker_line(&self.part_one_indices),
+            part1,
+            self.part_one_indices,
+            marker_line(&self.part_two_indices),
+            part2,
+            self.part_two_indices,
+        )
+    }
+}
+
+fn get_line_selections(line_iterator: Box<dyn Iterator<Item = String>>) -> Vec<LineSelection> {
+    line_iterator.map(LineSelection::new).collect()
+}
+
+fn run_benchmark(line_length: usize) {
+    // Fixed seed so the benchmark is repeatable
+    let mut rng = Lcg::new(0x2545_f491_4f6c_dd1d);
+    let lines = (0..10)
+        .map(|_| {
+            (0..line_length)
+                .map(|_| (b'1' + rng.next_below(9) as u8) as char)
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+    println!("\tDay 3 Benchmark\nLines:  {}\nLength: {}", lines.len(), line_length);
+    for algorithm in [Algorithm::SlotScan, Algorithm::MonotonicStack] {
+        let start = std::time::Instant::now();
+        let (part1, part2) = calculate_answers(Box::new(lines.clone().into_iter()), algorithm);
+        println!(
+            "{:?}: {} {} in {}us",
+            algorithm,
+            part1,
+            part2,
+            start.elapsed().as_micros()
+        );
+    }
+}
+
+fn read_input<P>(filename: P, validation: Validation) -> Result<Box<dyn Iterator<Item = String>>, Box<dyn Error>>
 where
     P: AsRef<Path>,
 {
-    Ok(Box::new(
-        get_lines_from_file(filename)?.map_while(|x| x.ok().filter(|x| x.len() > 11)),
-    ))
+    parse_lines(get_lines_from_file(filename)?, validation)
+}
+
+fn parse_lines<I>(lines: I, validation: Validation) -> Result<Box<dyn Iterator<Item = String>>, Box<dyn Error>>
+where
+    I: Iterator<Item = std::io::Result<String>> + 'static,
+{
+    match validation {
+        Validation::Strict => {
+            let mut valid_lines = Vec::new();
+            for (line_idx, line) in lines.enumerate() {
+                let line = line.map_err(|error| format!("Line {}: {}", line_idx + 1, error))?;
+                valid_lines.push(line);
+            }
+            // Blank lines are only allowed at the end of the file
+            while valid_lines.last().is_some_and(String::is_empty) {
+                valid_lines.pop();
+            }
+            for (line_idx, line) in valid_lines.iter().enumerate() {
+                validate_line(line).map_err(|error| format!("Line {}: {}", line_idx + 1, error))?;
+            }
+            Ok(Box::new(valid_lines.into_iter()))
+        }
+        Validation::Lenient => Ok(Box::new(
+            lines
+                .enumerate()
+                .map_while(|(line_idx, line)| match line {
+                    Err(error) => {
+                        eprintln!("Stopping at line {}: {}", line_idx + 1, error);
+                        None
+                    }
+                    Ok(line) => Some((line_idx, line)),
+                })
+                .filter_map(|(line_idx, line)| match validate_line(&line) {
+                    Err(error) if !line.is_empty() => {
+                        eprintln!("Skipping line {}: {}", line_idx + 1, error);
+                        None
+                    }
+                    Err(_) => None,
+                    Ok(()) => Some(line),
+                }),
+        )),
+    }
+}
+
+fn validate_line(line: &str) -> Result<(), String> {
+    if let Some((column, character)) = line.chars().enumerate().find(|(_, x)| !x.is_ascii_digit()) {
+        return Err(format!("Non-digit character {:?} in column {}", character, column + 1));
+    }
+    if line.len() < 12 {
+        return Err(format!("Only {} digits, at least 12 are needed", line.len()));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -75,8 +296,8 @@ mod test {
     use super::*;
     #[test]
     fn test_input_parse() {
-        let mut test_iterator = match read_input("test") {
-            Err(error) => panic!("Error occured reading test input: {}", error.to_string()),
+        let mut test_iterator = match read_input("test", Validation::Strict) {
+            Err(error) => panic!("Error occured reading test input: {}", error),
             Ok(iterator) => iterator,
         };
         assert_eq!(test_iterator.next(), Some(String::from("987654321111111")));
@@ -87,14 +308,97 @@ mod test {
     }
     #[test]
     fn test_part1() {
-        let test_iterator = read_input("test").unwrap();
-        let (part1, _part2) = calculate_answers(test_iterator);
+        let test_iterator = read_input("test", Validation::Strict).unwrap();
+        let (part1, _part2) = calculate_answers(test_iterator, Algorithm::SlotScan);
         assert_eq!(part1, 357);
     }
     #[test]
     fn test_part2() {
-        let test_iterator = read_input("test").unwrap();
-        let (_part1, part2) = calculate_answers(test_iterator);
+        let test_iterator = read_input("test", Validation::Strict).unwrap();
+        let (_part1, part2) = calculate_answers(test_iterator, Algorithm::SlotScan);
         assert_eq!(part2, 3121910778619);
     }
+    #[test]
+    fn test_stack_matches_slot_scan() {
+        let (part1, part2) = calculate_answers(
+            read_input("test", Validation::Strict).unwrap(),
+            Algorithm::MonotonicStack,
+        );
+        assert_eq!(part1, 357);
+        assert_eq!(part2, 3121910778619);
+        for line in [
+            "1234567890123",
+            "999999999999",
+            "112233445566778899",
+            "918273645546372819",
+        ] {
+            assert_eq!(
+                get_line_result(String::from(line)),
+                get_line_result_stack(String::from(line))
+            );
+        }
+    }
+    #[test]
+    fn test_line_selections() {
+        let selections = get_line_selections(read_input("test", Validation::Strict).unwrap());
+        assert_eq!(selections[0].part_one_indices, vec![0, 1]);
+        assert_eq!(selections[1].part_one_indices, vec![0, 14]);
+        assert_eq!(
+            selections[2].part_two_indices,
+            vec![2, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14]
+        );
+        assert_eq!(selections[3].values(), (92, 888911112111));
+        assert_eq!(
+            selections[1].render(),
+            "811111111111119\n^             ^  Part 1: 89 at [0, 14]\n\
+             ^^^^^^^^^^^   ^  Part 2: 811111111119 at [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 14]"
+        );
+    }
+    #[test]
+    fn test_validation() {
+        let to_lines = |lines: &[&str]| {
+            lines
+                .iter()
+                .map(|x| Ok(x.to_string()))
+                .collect::<Vec<std::io::Result<String>>>()
+                .into_iter()
+        };
+        let short = to_lines(&["987654321111111", "81111111", "234234234234278"]);
+        let error = parse_lines(short, Validation::Strict).err().unwrap();
+        assert_eq!(error.to_string(), "Line 2: Only 8 digits, at least 12 are needed");
+        let non_digit = to_lines(&["987654321111111", "8111111111x1119"]);
+        let error = parse_lines(non_digit, Validation::Strict).err().unwrap();
+        assert_eq!(error.to_string(), "Line 2: Non-digit character 'x' in column 11");
+        let blank_in_middle = to_lines(&["987654321111111", "", "234234234234278"]);
+        assert!(parse_lines(blank_in_middle, Validation::Strict).is_err());
+        let read_error = vec![
+            Ok(String::from("987654321111111")),
+            Err(std::io::Error::other("bad read")),
+        ];
+        let error = parse_lines(read_error.into_iter(), Validation::Strict).err().unwrap();
+        assert_eq!(error.to_string(), "Line 2: bad read");
+
+        let mixed = to_lines(&[
+            "987654321111111",
+            "81111111",
+            "2342342x4234278",
+            "",
+            "818181911112111",
+            "",
+        ]);
+        let lines = parse_lines(mixed, Validation::Lenient).unwrap().collect::<Vec<_>>();
+        assert_eq!(lines, vec!["987654321111111", "818181911112111"]);
+        let read_error = vec![
+            Ok(String::from("987654321111111")),
+            Ok(String::from("81111111")),
+            Err(std::io::Error::other("bad read")),
+            Ok(String::from("818181911112111")),
+        ];
+        let lines = parse_lines(read_error.into_iter(), Validation::Lenient)
+            .unwrap()
+            .collect::<Vec<_>>();
+        assert_eq!(lines, vec!["987654321111111"]);
+        let trailing_blank = to_lines(&["987654321111111", "", ""]);
+        assert_eq!(parse_lines(trailing_blank, Validation::Strict).unwrap().count(), 1);
+    }
 }
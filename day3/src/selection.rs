@@ -0,0 +1,217 @@
+// Engine for picking `count` digits, in order, out of a line of ASCII digits.
+// All selections are returned as the indices of the picked digits.
+type Int = i64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Objective {
+    Maximise,
+    Minimise,
+}
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Constraints {
+    // No two picked digits can be next to each other in the line
+    pub no_adjacent: bool,
+    // Digit values (0-9) that can never be picked
+    pub forbidden_digits: Vec<u8>,
+}
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectionRule {
+    pub objective: Objective,
+    pub count: usize,
+    pub constraints: Constraints,
+}
+impl SelectionRule {
+    pub fn new(objective: Objective, count: usize) -> SelectionRule {
+        SelectionRule {
+            objective,
+            count,
+            constraints: Constraints::default(),
+        }
+    }
+    fn is_unconstrained(&self) -> bool { self.constraints == Constraints::default() }
+}
+
+pub fn select_largest(digits: &[u8], count: usize) -> Vec<usize> {
+    // Pop any smaller digit when a larger one arrives, as long as enough digits can still be dropped.
+    // The stack never needs more than `count` digits, anything that doesn't fit is dropped instead.
+    let mut removals_left = digits.len().saturating_sub(count);
+    let mut stack: Vec<usize> = Vec::with_capacity(count);
+    for (idx, &digit) in digits.iter().enumerate() {
+        while removals_left > 0 && stack.last().is_some_and(|&top| digits[top] < digit) {
+            stack.pop();
+            removals_left -= 1;
+        }
+        if stack.len() < count {
+            stack.push(idx);
+        } else {
+            removals_left -= 1;
+        }
+    }
+    stack
+}
+
+// Most digits that indices_to_value can turn into an Int without overflowing
+pub const MAX_VALUE_DIGITS: usize = 18;
+
+pub fn indices_to_value(digits: &[u8], indices: &[usize]) -> Int {
+    indices
+        .iter()
+        .fold(0, |lhs, &idx| lhs * 10 + (digits[idx] - b'0') as Int)
+}
+
+pub fn select(digits: &[u8], rule: &SelectionRule) -> Option<Vec<usize>> {
+    if rule.objective == Objective::Maximise && rule.is_unconstrained() {
+        // Fast path, the stack is linear and doesn't need the lookup tables
+        return (digits.len() >= rule.count).then(|| select_largest(digits, rule.count));
+    }
+    select_candidates(digits, rule, 1).pop()
+}
+
+pub fn select_candidates(digits: &[u8], rule: &SelectionRule, num_candidates: usize) -> Vec<Vec<usize>> {
+    // Picks digits greedily from the front. Taking the earliest occurrence of a digit always leaves the most
+    // room for the rest of the picks, so each digit only needs to be tried once per pick, and trying them in
+    // best-first order gives the candidates in order without duplicates.
+    let mut candidates = Vec::new();
+    if num_candidates == 0 {
+        return candidates;
+    }
+    let search = CandidateSearch::new(digits, rule);
+    let mut chosen = Vec::with_capacity(rule.count);
+    search.collect(0, rule.count, &mut chosen, &mut candidates, num_candidates);
+    candidates
+}
+
+struct CandidateSearch {
+    // Distance to the next pickable index after a pick
+    step: usize,
+    digit_order: Vec<u8>,
+    // occurrences[d] holds the indices of digit d in order, so the next one after any index is a binary search.
+    // One index per digit in total, rather than a table of 10 per digit.
+    occurrences: [Vec<usize>; 10],
+    // max_picks[i] is the most digits that can be picked from index i onwards
+    max_picks: Vec<usize>,
+}
+impl CandidateSearch {
+    fn new(digits: &[u8], rule: &SelectionRule) -> CandidateSearch {
+        let num_digits = digits.len();
+        let step = if rule.constraints.no_adjacent { 2 } else { 1 };
+        let mut allowed = [true; 10];
+        // Anything that isn't a digit value can't be in the line anyway
+        rule.constraints
+            .forbidden_digits
+            .iter()
+            .filter(|&&digit| digit < 10)
+            .for_each(|&digit| allowed[digit as usize] = false);
+        let digit_order = match rule.objective {
+            Objective::Maximise => (0..10).rev().filter(|&x| allowed[x as usize]).collect(),
+            Objective::Minimise => (0..10).filter(|&x| allowed[x as usize]).collect(),
+        };
+
+        let mut occurrences: [Vec<usize>; 10] = Default::default();
+        for (idx, &digit) in digits.iter().enumerate() {
+            let digit = (digit - b'0') as usize;
+            if allowed[digit] {
+                occurrences[digit].push(idx);
+            }
+        }
+        let mut max_picks = vec![0; num_digits + step + 1];
+        for idx in (0..num_digits).rev() {
+            let digit = (digits[idx] - b'0') as usize;
+            max_picks[idx] = match allowed[digit] {
+                true => max_picks[idx + 1].max(1 + max_picks[idx + step]),
+                false => max_picks[idx + 1],
+            };
+        }
+        CandidateSearch {
+            step,
+            digit_order,
+            occurrences,
+            max_picks,
+        }
+    }
+    fn collect(
+        &self,
+        start: usize,
+        remaining: usize,
+        chosen: &mut Vec<usize>,
+        candidates: &mut Vec<Vec<usize>>,
+        num_candidates: usize,
+    ) {
+        if remaining == 0 {
+            candidates.push(chosen.clone());
+            return;
+        }
+        for &digit in self.digit_order.iter() {
+            if candidates.len() >= num_candidates {
+                return;
+            }
+            let occurrences = &self.occurrences[digit as usize];
+            let Some(&idx) = occurrences.get(occurrences.partition_point(|&x| x < start)) else {
+                continue;
+            };
+            // A later occurrence can't fit more picks after it, so if this one doesn't work, none will
+            if self.max_picks[idx + self.step] < remaining - 1 {
+                continue;
+            }
+            chosen.push(idx);
+            self.collect(idx + self.step, remaining - 1, chosen, candidates, num_candidates);
+            chosen.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn values(digits: &str, rule: &SelectionRule, num_candidates: usize) -> Vec<Int> {
+        select_candidates(digits.as_bytes(), rule, num_candidates)
+            .iter()
+            .map(|indices| indices_to_value(digits.as_bytes(), indices))
+            .collect()
+    }
+    #[test]
+    fn test_matches_stack() {
+        let rule = SelectionRule::new(Objective::Maximise, 12);
+        for line in [
+            "987654321111111",
+            "811111111111119",
+            "234234234234278",
+            "818181911112111",
+        ] {
+            assert_eq!(
+                select_candidates(line.as_bytes(), &rule, 1),
+                vec![select_largest(line.as_bytes(), 12)]
+            );
+        }
+    }
+    #[test]
+    fn test_minimise() {
+        let rule = SelectionRule::new(Objective::Minimise, 4);
+        assert_eq!(values("818181911112111", &rule, 1), vec![1111]);
+        assert_eq!(values("987654321", &rule, 1), vec![4321]);
+        assert_eq!(values("3052", &rule, 1), vec![3052]);
+        assert_eq!(select("305".as_bytes(), &rule), None);
+    }
+    #[test]
+    fn test_constraints() {
+        let mut rule = SelectionRule::new(Objective::Maximise, 3);
+        rule.constraints.no_adjacent = true;
+        assert_eq!(values("99919", &rule, 1), vec![999]);
+        assert_eq!(select("9991".as_bytes(), &rule), None);
+        assert_eq!(values("1234567", &rule, 1), vec![357]);
+        rule.constraints.no_adjacent = false;
+        rule.constraints.forbidden_digits = vec![9, 7];
+        assert_eq!(values("19783465", &rule, 1), vec![865]);
+        assert_eq!(values("97979", &rule, 1), vec![]);
+        rule.constraints.forbidden_digits = vec![10, 255, 9];
+        assert_eq!(values("19783465", &rule, 1), vec![865]);
+    }
+    #[test]
+    fn test_top_candidates() {
+        let rule = SelectionRule::new(Objective::Maximise, 2);
+        assert_eq!(values("1213", &rule, 10), vec![23, 21, 13, 12, 11]);
+        assert_eq!(values("1213", &rule, 2), vec![23, 21]);
+        let rule = SelectionRule::new(Objective::Minimise, 2);
+        assert_eq!(values("1213", &rule, 3), vec![11, 12, 13]);
+    }
+}
@@ -4,7 +4,13 @@ pub fn main() {
     let start = std::time::Instant::now();
     let input = read_input("input").unwrap();
     let parsed = start.elapsed();
-    let (part1, part2) = calculate_answers(input);
+    let (part1, part2) = match calculate_answers(input) {
+        Err(error) => {
+            println!("Error occured processing day 3 input: {}", error);
+            return;
+        }
+        Ok(answers) => answers,
+    };
     let elapsed = start.elapsed();
     println!(
         "\tDay 3 Unsafe\nPart 1: {}\nPart 2: {}\nTime:   {}\nIO:     {}\nCalc:   {}",
@@ -15,24 +21,108 @@ pub fn main() {
         (elapsed - parsed).as_micros()
     );
 }
-fn calculate_answers(raw_data: Vec<u8>) -> (Int, Int) {
-    raw_data
-        .as_chunks::<101>()
-        .0
-        .into_iter()
-        .map(get_line_result::<101>)
-        .reduce(|lhs, rhs| (lhs.0 + rhs.0, lhs.1 + rhs.1))
-        .unwrap()
+fn calculate_answers(mut raw_data: Vec<u8>) -> Result<(Int, Int), Box<dyn Error>> {
+    // Width of a line is taken from the first line, including its line ending (LF or CRLF).
+    let first_newline = raw_data.iter().position(|&x| x == b'\n');
+    let line_ending: &[u8] = match first_newline {
+        Some(idx) if idx > 0 && raw_data[idx - 1] == b'\r' => b"\r\n",
+        _ => b"\n",
+    };
+    // Missing final newline, add one so the last line is a full chunk
+    if !raw_data.is_empty() && !raw_data.ends_with(b"\n") {
+        raw_data.extend_from_slice(line_ending);
+    }
+    let line_size = first_newline.map_or(raw_data.len(), |idx| idx + 1);
+    if line_size <= line_ending.len() {
+        return Err("First line of input is empty".into());
+    }
+    // Every line has to be as wide as the first, so a short first line is the only one to report. Same message
+    // as the strict reader in main.rs.
+    let num_digits = line_size - line_ending.len();
+    if num_digits < 12 {
+        return Err(format!("Line 1: Only {} digits, at least 12 are needed", num_digits).into());
+    }
+    // Dispatch the common widths to monomorphised versions, so the line length is known at compile time
+    match (line_size, line_ending.len()) {
+        (101, 1) => sum_fixed_lines::<101, 1>(&raw_data),
+        (102, 2) => sum_fixed_lines::<102, 2>(&raw_data),
+        (16, 1) => sum_fixed_lines::<16, 1>(&raw_data),
+        (17, 2) => sum_fixed_lines::<17, 2>(&raw_data),
+        _ => sum_lines(&raw_data, line_size, line_ending),
+    }
+}
+
+fn sum_fixed_lines<const LINE_SIZE: usize, const ENDING_SIZE: usize>(
+    raw_data: &[u8],
+) -> Result<(Int, Int), Box<dyn Error>> {
+    let (lines, remainder) = raw_data.as_chunks::<LINE_SIZE>();
+    check_remainder(lines.len(), remainder)?;
+    let mut answers = (0, 0);
+    for (line_idx, line) in lines.iter().enumerate() {
+        let (digits, ending) = line.split_at(LINE_SIZE - ENDING_SIZE);
+        check_line(line_idx, digits, ending, LINE_SIZE)?;
+        let line_result = get_line_result(digits);
+        answers = (answers.0 + line_result.0, answers.1 + line_result.1);
+    }
+    Ok(answers)
+}
+
+fn sum_lines(raw_data: &[u8], line_size: usize, line_ending: &[u8]) -> Result<(Int, Int), Box<dyn Error>> {
+    let lines = raw_data.chunks_exact(line_size);
+    check_remainder(lines.len(), lines.remainder())?;
+    let mut answers = (0, 0);
+    for (line_idx, line) in lines.enumerate() {
+        let (digits, ending) = line.split_at(line_size - line_ending.len());
+        check_line(line_idx, digits, ending, line_size)?;
+        let line_result = get_line_result(digits);
+        answers = (answers.0 + line_result.0, answers.1 + line_result.1);
+    }
+    Ok(answers)
+}
+
+fn check_line(line_idx: usize, digits: &[u8], ending: &[u8], line_size: usize) -> Result<(), Box<dyn Error>> {
+    // Any line that isn't the width of the first puts a newline somewhere else in the chunk. Short lines can
+    // still add up to a whole chunk, so the digits are checked as well as the ending.
+    let bad_byte = digits.iter().find(|x| !x.is_ascii_digit());
+    match (ending, bad_byte) {
+        (b"\n" | b"\r\n", None) => Ok(()),
+        (_, Some(byte)) if *byte != b'\n' => Err(format!(
+            "Line {} contains {:?}, expected only digits",
+            line_idx + 1,
+            *byte as char
+        )
+        .into()),
+        _ => Err(format!(
+            "Line {} is not {} bytes long (including line ending)",
+            line_idx + 1,
+            line_size
+        )
+        .into()),
+    }
+}
+
+fn check_remainder(num_lines: usize, remainder: &[u8]) -> Result<(), Box<dyn Error>> {
+    if remainder.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Input ends with a partial line of {} bytes after line {}",
+            remainder.len(),
+            num_lines
+        )
+        .into())
+    }
 }
 
-fn get_line_result<const LINE_SIZE: usize>(line: &[u8; LINE_SIZE]) -> (Int, Int) {
+#[inline(always)]
+fn get_line_result(digits: &[u8]) -> (Int, Int) {
     let mut part_two_digits: [Int; 12] = [0; 12];
     let mut part_one_digits: [Int; 2] = [0; 2];
     // Stores the digit of the best
-    for (next_digit_idx, next_digit_char) in line.into_iter().rev().skip(1).rev().enumerate() {
+    for (next_digit_idx, next_digit_char) in digits.iter().enumerate() {
         let next_digit_value = *next_digit_char as Int - '0' as Int;
         // If we're close to the end, don't replace early digits, start from this idx:
-        let start_index_for_replace = 11 - (line.len() - next_digit_idx - 2).min(11);
+        let start_index_for_replace = 11 - (digits.len() - next_digit_idx - 1).min(11);
         for index in start_index_for_replace..12 {
             if next_digit_value > part_two_digits[index] {
                 part_two_digits[index] = next_digit_value;
@@ -41,7 +131,7 @@ fn get_line_result<const LINE_SIZE: usize>(line: &[u8; LINE_SIZE]) -> (Int, Int)
             }
         }
         // If at the end of a line, don't replace the first digit for part 1.
-        if next_digit_value > part_one_digits[0] && next_digit_idx != line.len() - 2 {
+        if next_digit_value > part_one_digits[0] && next_digit_idx != digits.len() - 1 {
             part_one_digits[0] = next_digit_value;
             part_one_digits[1] = 0;
         } else if next_digit_value > part_one_digits[1] {
@@ -49,10 +139,10 @@ fn get_line_result<const LINE_SIZE: usize>(line: &[u8; LINE_SIZE]) -> (Int, Int)
         }
     }
     // Convert digit arrays into an integer
-    return (
+    (
         part_one_digits.into_iter().reduce(|lhs, rhs| lhs * 10 + rhs).unwrap(),
         part_two_digits.into_iter().reduce(|lhs, rhs| lhs * 10 + rhs).unwrap(),
-    );
+    )
 }
 
 fn read_input<P>(filename: P) -> Result<Vec<u8>, Box<dyn Error>>
@@ -67,15 +157,47 @@ mod test {
     use super::*;
     #[test]
     fn test_alt() {
-        let test_iterator = read_input("test").unwrap();
-        let (part1, part2) = test_iterator
-            .as_chunks::<16>()
-            .0
-            .into_iter()
-            .map(get_line_result::<16>)
-            .reduce(|lhs, rhs| (lhs.0 + rhs.0, lhs.1 + rhs.1))
-            .unwrap();
+        let (part1, part2) = calculate_answers(read_input("test").unwrap()).unwrap();
         assert_eq!(part1, 357);
         assert_eq!(part2, 3121910778619);
     }
+    #[test]
+    fn test_line_endings() {
+        let lf = std::fs::read_to_string("test").unwrap();
+        let crlf = lf.replace('\n', "\r\n");
+        let unterminated = lf.trim_end();
+        for input in [crlf.as_str(), unterminated, crlf.trim_end()] {
+            assert_eq!(
+                calculate_answers(input.as_bytes().to_vec()).unwrap(),
+                (357, 3121910778619)
+            );
+        }
+    }
+    #[test]
+    fn test_other_widths() {
+        // 13 digits per line isn't a monomorphised width, so this uses the runtime width
+        let input = b"1234567890123\n9876543210987\n".to_vec();
+        assert_eq!(
+            calculate_answers(input).unwrap(),
+            (93 + 99, 234567890123 + 987654321987)
+        );
+        assert!(calculate_answers(b"1234567890123\n987654321098\n".to_vec()).is_err());
+        assert!(calculate_answers(b"1234567890123\n98765432109876\n".to_vec()).is_err());
+        assert!(calculate_answers(b"987654321111111\n81111111111119\n234234234234278\n".to_vec()).is_err());
+        // Two short lines that fill a whole chunk between them
+        assert!(calculate_answers(b"987654321111111\n1234567\n1234567\n".to_vec()).is_err());
+        assert_eq!(
+            calculate_answers(b"1234567890123\n9876\r43210987\n".to_vec())
+                .unwrap_err()
+                .to_string(),
+            "Line 2 contains '\\r', expected only digits"
+        );
+        // Part 2 needs 12 digits, so narrower lines are rejected rather than giving the line's own value
+        for (input, num_digits) in [("81111111\n23423423\n", 8), ("12345678901\r\n", 11), ("1", 1)] {
+            assert_eq!(
+                calculate_answers(input.as_bytes().to_vec()).unwrap_err().to_string(),
+                format!("Line 1: Only {} digits, at least 12 are needed", num_digits)
+            );
+        }
+    }
 }
@@ -0,0 +1,155 @@
+use shared::Grid;
+
+// One bit per cell, each row is stored as a run of 64-bit words.
+// Bit i of word w in a row is column 64 * w + i, bits past the width are always 0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+impl BitGrid {
+    pub fn from_grid(grid: &Grid<bool>) -> BitGrid {
+        let words_per_row = grid.width().div_ceil(64);
+        let mut words = vec![0; words_per_row * grid.height()];
+        for ((row, col), _) in grid.iter().filter(|(_, cell)| **cell) {
+            words[row * words_per_row + col / 64] |= 1 << (col % 64);
+        }
+        BitGrid {
+            width: grid.width(),
+            height: grid.height(),
+            words_per_row,
+            words,
+        }
+    }
+    pub fn width(&self) -> usize { self.width }
+    pub fn height(&self) -> usize { self.height }
+    pub fn row(&self, row: usize) -> &[u64] { &self.words[row * self.words_per_row..(row + 1) * self.words_per_row] }
+    pub fn row_mut(&mut self, row: usize) -> &mut [u64] {
+        &mut self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+    // Row with `halo` extra columns on each side taken from the other end of the row, for wrapping round.
+    // Bit i of the result is column (i - halo) mod width.
+    pub fn wrapped_row(&self, row: usize, halo: usize) -> Vec<u64> {
+        let row = self.row(row);
+        let extended_width = self.width + 2 * halo;
+        let mut words = (0..extended_width.div_ceil(64))
+            .map(|word_idx| shifted_word(row, word_idx, ColumnShift::new(-(halo as isize))))
+            .collect::<Vec<_>>();
+        for idx in (0..halo).chain(halo + self.width..extended_width) {
+            let col = (idx as isize - halo as isize).rem_euclid(self.width as isize) as usize;
+            words[idx / 64] |= (row[col / 64] >> (col % 64) & 1) << (idx % 64);
+        }
+        words
+    }
+}
+
+// Column offset split into whole words and leftover bits, so shifting doesn't need a division per word
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColumnShift {
+    words: isize,
+    bits: u32,
+}
+impl ColumnShift {
+    pub fn new(offset: isize) -> ColumnShift {
+        ColumnShift {
+            words: offset.div_euclid(64),
+            bits: offset.rem_euclid(64) as u32,
+        }
+    }
+}
+
+// Word `word_idx` of a row, shifted so bit i holds column (64 * word_idx + i + offset). Columns outside the row are 0.
+#[inline]
+pub fn shifted_word(row: &[u64], word_idx: usize, shift: ColumnShift) -> u64 {
+    let source_idx = word_idx as isize + shift.words;
+    // Negative indices wrap round to huge ones, so they're also caught by get
+    let word = |idx: isize| row.get(idx as usize).copied().unwrap_or(0);
+    match shift.bits {
+        0 => word(source_idx),
+        bits => (word(source_idx) >> bits) | (word(source_idx + 1) << (64 - bits)),
+    }
+}
+
+// Bit-sliced counter, bit i of `bits[n]` is bit n of the count for cell i. Holds counts below 2^PLANES.
+// The number of planes is a const so the counter can stay in registers.
+#[derive(Clone, Copy)]
+pub struct SlicedCounter<const PLANES: usize> {
+    bits: [u64; PLANES],
+}
+impl<const PLANES: usize> SlicedCounter<PLANES> {
+    pub fn new() -> SlicedCounter<PLANES> { SlicedCounter { bits: [0; PLANES] } }
+    // Adds one to the count of every cell with its bit set in `input` (ripple carry, one bit plane at a time)
+    #[inline]
+    pub fn add(&mut self, input: u64) {
+        let mut carry = input;
+        for bit in self.bits.iter_mut() {
+            let next_carry = *bit & carry;
+            *bit ^= carry;
+            carry = next_carry;
+        }
+    }
+    // Cells whose count is less than `threshold`, compared one bit plane at a time from the top
+    pub fn below(&self, threshold: usize) -> u64 {
+        if threshold >> PLANES != 0 {
+            // Threshold is bigger than anything the counter can hold
+            return u64::MAX;
+        }
+        let mut less = 0;
+        let mut equal = u64::MAX;
+        for (plane, bit) in self.bits.iter().enumerate().rev() {
+            if threshold >> plane & 1 == 1 {
+                less |= equal & !bit;
+                equal &= bit;
+            } else {
+                equal &= !bit;
+            }
+        }
+        less
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_from_grid() {
+        let mut layout = Grid::new(130, 2, false);
+        layout[(1, 0)] = true;
+        layout[(1, 63)] = true;
+        layout[(1, 64)] = true;
+        layout[(1, 129)] = true;
+        let grid = BitGrid::from_grid(&layout);
+        assert_eq!(grid.row(0), [0, 0, 0]);
+        assert_eq!(grid.row(1), [1 | 1 << 63, 1, 1 << 1]);
+        // Left neighbours (offset -1) and right neighbours (offset 1)
+        assert_eq!(shifted_word(grid.row(1), 1, ColumnShift::new(-1)), 1 << 1 | 1);
+        assert_eq!(shifted_word(grid.row(1), 0, ColumnShift::new(1)), 1 << 62 | 1 << 63);
+        assert_eq!(shifted_word(grid.row(1), 0, ColumnShift::new(64)), 1);
+        assert_eq!(shifted_word(grid.row(1), 2, ColumnShift::new(-65)), 0b11);
+        assert_eq!(shifted_word(grid.row(1), 2, ColumnShift::new(2)), 0);
+    }
+    #[test]
+    fn test_wrapped_row() {
+        let mut layout = Grid::new(70, 1, false);
+        layout[(0, 0)] = true;
+        layout[(0, 68)] = true;
+        let grid = BitGrid::from_grid(&layout);
+        // Two columns either side, so the row is columns 68, 69, 0, 1, ..., 69, 0, 1
+        assert_eq!(grid.wrapped_row(0, 2), [1 | 1 << 2, 1 << 6 | 1 << 8]);
+    }
+    #[test]
+    fn test_sliced_counter() {
+        let mut counter = SlicedCounter::<4>::new();
+        // Cell i gets i added, for i in 0..9
+        for input in 1..9 {
+            counter.add((input..9).map(|x| 1 << x).sum());
+        }
+        assert_eq!(counter.below(4) & 0x1ff, 0b0_0000_1111);
+        assert_eq!(counter.below(0) & 0x1ff, 0);
+        assert_eq!(counter.below(9) & 0x1ff, 0b1_1111_1111);
+        assert_eq!(counter.below(100) & 0x1ff, 0b1_1111_1111);
+        assert_eq!(SlicedCounter::<4>::new().below(1), u64::MAX);
+    }
+}
@@ -0,0 +1,162 @@
+use shared::Grid;
+use std::collections::{HashMap, HashSet};
+
+use crate::history::PeelHistory;
+use crate::rule::RemovalRule;
+
+// A warehouse floor that rolls can be peeled from, independent of how the cells are stored
+pub trait Floor {
+    type Position: Copy;
+    fn rolls(&self) -> Vec<Self::Position>;
+    fn is_roll(&self, position: Self::Position) -> bool;
+    fn remove(&mut self, position: Self::Position);
+    // Fills `neighbours` with the cells at each offset from `position` that are on the floor.
+    // The offset (0, 0) is always skipped, even if the rule includes the cell itself.
+    fn neighbours(&self, position: Self::Position, offsets: &[(isize, isize)], neighbours: &mut Vec<Self::Position>);
+    // Neighbour count stored for a roll, used by the worklist
+    fn neighbour_count_mut(&mut self, position: Self::Position) -> &mut u32;
+
+    fn count_neighbours(
+        &self,
+        position: Self::Position,
+        rule: &RemovalRule,
+        offsets: &[(isize, isize)],
+        neighbours: &mut Vec<Self::Position>,
+    ) -> usize {
+        self.neighbours(position, offsets, neighbours);
+        let other_rolls = neighbours.iter().filter(|&&neighbour| self.is_roll(neighbour)).count();
+        other_rolls + rule.include_self as usize
+    }
+}
+
+// Dense grid, either with everything past the edges empty or wrapping round (toroidal)
+pub struct DenseFloor {
+    rolls: Grid<bool>,
+    neighbour_counts: Grid<u32>,
+    wrap: bool,
+}
+impl DenseFloor {
+    pub fn new(rolls: Grid<bool>, wrap: bool) -> DenseFloor {
+        let neighbour_counts = Grid::new(rolls.width(), rolls.height(), 0);
+        DenseFloor {
+            rolls,
+            neighbour_counts,
+            wrap,
+        }
+    }
+}
+impl Floor for DenseFloor {
+    type Position = (usize, usize);
+    fn rolls(&self) -> Vec<(usize, usize)> {
+        self.rolls
+            .iter()
+            .filter(|(_, is_roll)| **is_roll)
+            .map(|(position, _)| position)
+            .collect()
+    }
+    fn is_roll(&self, position: (usize, usize)) -> bool { self.rolls[position] }
+    fn remove(&mut self, position: (usize, usize)) { self.rolls[position] = false }
+    fn neighbours(&self, (row, col): (usize, usize), offsets: &[(isize, isize)], neighbours: &mut Vec<(usize, usize)>) {
+        let (height, width) = (self.rolls.height() as isize, self.rolls.width() as isize);
+        neighbours.clear();
+        let positions = offsets
+            .iter()
+            .filter(|&&offset| offset != (0, 0))
+            .map(|(row_offset, col_offset)| (row as isize + row_offset, col as isize + col_offset))
+            .filter_map(|(row, col)| match self.wrap {
+                true => Some((row.rem_euclid(height), col.rem_euclid(width))),
+                false => self.rolls.in_bounds(row, col).then_some((row, col)),
+            })
+            .map(|(row, col)| (row as usize, col as usize));
+        neighbours.extend(positions);
+    }
+    fn neighbour_count_mut(&mut self, position: (usize, usize)) -> &mut u32 { &mut self.neighbour_counts[position] }
+}
+
+// Only the rolls are stored, for huge floors that are mostly empty. There are no edges.
+pub struct SparseFloor {
+    // Every roll, with its neighbour count
+    rolls: HashMap<(i64, i64), u32>,
+}
+impl SparseFloor {
+    pub fn new(rolls: HashSet<(i64, i64)>) -> SparseFloor {
+        SparseFloor {
+            rolls: rolls.into_iter().map(|position| (position, 0)).collect(),
+        }
+    }
+}
+impl Floor for SparseFloor {
+    type Position = (i64, i64);
+    fn rolls(&self) -> Vec<(i64, i64)> { self.rolls.keys().copied().collect() }
+    fn is_roll(&self, position: (i64, i64)) -> bool { self.rolls.contains_key(&position) }
+    fn remove(&mut self, position: (i64, i64)) { self.rolls.remove(&position); }
+    fn neighbours(&self, (row, col): (i64, i64), offsets: &[(isize, isize)], neighbours: &mut Vec<(i64, i64)>) {
+        neighbours.clear();
+        // Positions past the edge of the i64 range can't hold a roll, so they're left out
+        let positions = offsets
+            .iter()
+            .filter(|&&offset| offset != (0, 0))
+            .filter_map(|&(row_offset, col_offset)| {
+                Some((row.checked_add(row_offset as i64)?, col.checked_add(col_offset as i64)?))
+            });
+        neighbours.extend(positions);
+    }
+    fn neighbour_count_mut(&mut self, position: (i64, i64)) -> &mut u32 { self.rolls.get_mut(&position).unwrap() }
+}
+
+pub fn peel_rescan<F: Floor>(floor: &mut F, rule: &RemovalRule) -> PeelHistory<F::Position> {
+    // Same as remove_rolls, for floors that can't be stored as a bit grid
+    let offsets = rule.offsets();
+    let mut neighbours = Vec::with_capacity(offsets.len());
+    let mut history = PeelHistory::new();
+    loop {
+        let removable = floor
+            .rolls()
+            .into_iter()
+            .filter(|&roll| floor.count_neighbours(roll, rule, &offsets, &mut neighbours) < rule.threshold)
+            .collect::<Vec<_>>();
+        if removable.is_empty() {
+            history.remaining = floor.rolls();
+            return history;
+        }
+        removable.iter().for_each(|&roll| floor.remove(roll));
+        history.push_round(removable);
+    }
+}
+
+pub fn peel_worklist<F: Floor>(floor: &mut F, rule: &RemovalRule) -> PeelHistory<F::Position> {
+    // Every roll is removed in the same round as the rescan would remove it, but only the neighbours
+    // of removed rolls are looked at again, so each cell is only revisited when a neighbour is removed.
+    let offsets = rule.offsets();
+    let mut neighbours = Vec::with_capacity(offsets.len());
+    let mut removable = Vec::new();
+    for roll in floor.rolls() {
+        let neighbour_count = floor.count_neighbours(roll, rule, &offsets, &mut neighbours);
+        *floor.neighbour_count_mut(roll) = neighbour_count as u32;
+        if neighbour_count < rule.threshold {
+            removable.push(roll);
+        }
+    }
+    let mut history = PeelHistory::new();
+    while !removable.is_empty() {
+        // Remove the whole round before updating counts, so rolls aren't counted as neighbours of themselves
+        removable.iter().for_each(|&roll| floor.remove(roll));
+        let mut next_removable = Vec::new();
+        for &roll in removable.iter() {
+            floor.neighbours(roll, &offsets, &mut neighbours);
+            for &neighbour in neighbours.iter() {
+                if floor.is_roll(neighbour) {
+                    let neighbour_count = floor.neighbour_count_mut(neighbour);
+                    *neighbour_count -= 1;
+                    // Only queue a roll once, when it first drops below the threshold
+                    if *neighbour_count as usize + 1 == rule.threshold {
+                        next_removable.push(neighbour);
+                    }
+                }
+            }
+        }
+        history.push_round(std::mem::replace(&mut removable, next_removable));
+    }
+    history.remaining = floor.rolls();
+    history
+}
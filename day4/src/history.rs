@@ -0,0 +1,102 @@
+use shared::Grid;
+
+// Record of peeling a floor. The round a roll is removed in is its peel layer, starting from layer 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeelHistory<P> {
+    // Removed rolls with their layer, in the order they were removed
+    pub layers: Vec<(P, usize)>,
+    // Number of rolls removed in each round, ending with the last round that removed anything
+    pub removed_per_round: Vec<usize>,
+    // Rolls left once nothing else can be removed
+    pub remaining: Vec<P>,
+}
+impl<P> PeelHistory<P> {
+    pub fn new() -> PeelHistory<P> {
+        PeelHistory {
+            layers: Vec::new(),
+            removed_per_round: Vec::new(),
+            remaining: Vec::new(),
+        }
+    }
+    // Adds the next round of removals
+    pub fn push_round(&mut self, removed: impl IntoIterator<Item = P>) {
+        let layer = self.removed_per_round.len() + 1;
+        let num_removed_before = self.layers.len();
+        self.layers
+            .extend(removed.into_iter().map(|position| (position, layer)));
+        self.removed_per_round.push(self.layers.len() - num_removed_before);
+    }
+    // Removed in the first round, and in total
+    pub fn answers(&self) -> (i32, i32) {
+        let first_round = self.removed_per_round.first().copied().unwrap_or(0);
+        (first_round as i32, self.layers.len() as i32)
+    }
+}
+
+// Layers 1-9, then a-z, then A-Z. Anything deeper is shown as '+'.
+pub fn layer_char(layer: usize) -> char {
+    match layer {
+        1..=9 => (b'0' + layer as u8) as char,
+        10..=35 => (b'a' + (layer - 10) as u8) as char,
+        36..=61 => (b'A' + (layer - 36) as u8) as char,
+        _ => '+',
+    }
+}
+
+// Map of the floor with each removed roll shown as its layer and remaining rolls as '@'
+pub fn render_layers(history: &PeelHistory<(usize, usize)>, width: usize, height: usize) -> String {
+    let mut map = render_remaining(history, width, height);
+    for &(position, layer) in history.layers.iter() {
+        map[position] = layer_char(layer);
+    }
+    map.to_string()
+}
+// Map of the final stable layout, in the same format as the input
+pub fn render_remaining(history: &PeelHistory<(usize, usize)>, width: usize, height: usize) -> Grid<char> {
+    let mut map = Grid::new(width, height, '.');
+    history.remaining.iter().for_each(|&position| map[position] = '@');
+    map
+}
+// For floors too big to draw, "row,col,layer" for each removed roll and "row,col" for each remaining roll
+pub fn render_layer_list(history: &PeelHistory<(i64, i64)>) -> String {
+    let removed = history
+        .layers
+        .iter()
+        .map(|((row, col), layer)| format!("{},{},{}\n", row, col, layer));
+    let remaining = history.remaining.iter().map(|(row, col)| format!("{},{}\n", row, col));
+    removed
+        .chain(std::iter::once(String::from("Remaining:\n")))
+        .chain(remaining)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_history() {
+        let mut history = PeelHistory::new();
+        history.push_round([(0, 0), (0, 2)]);
+        history.push_round([(1, 1)]);
+        history.remaining = vec![(1, 0)];
+        assert_eq!(history.answers(), (2, 3));
+        assert_eq!(history.removed_per_round, vec![2, 1]);
+        assert_eq!(render_layers(&history, 3, 2), "1.1\n@2.\n");
+        assert_eq!(render_remaining(&history, 3, 2).to_string(), "...\n@..\n");
+        assert_eq!(PeelHistory::<(i64, i64)>::new().answers(), (0, 0));
+        let mut history = PeelHistory::new();
+        history.push_round([(-5, 0), (-5, 2_000_000_000)]);
+        history.remaining = vec![(-4, 0)];
+        assert_eq!(
+            render_layer_list(&history),
+            "-5,0,1\n-5,2000000000,1\nRemaining:\n-4,0\n"
+        );
+    }
+    #[test]
+    fn test_layer_char() {
+        assert_eq!(layer_char(1), '1');
+        assert_eq!(layer_char(10), 'a');
+        assert_eq!(layer_char(36), 'A');
+        assert_eq!(layer_char(62), '+');
+    }
+}
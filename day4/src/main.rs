@@ -1,111 +1,593 @@
+mod bitgrid;
+mod floor;
+mod history;
+mod rule;
+mod visualise;
+use bitgrid::{BitGrid, ColumnShift, SlicedCounter, shifted_word};
+use floor::{DenseFloor, Floor, SparseFloor, peel_rescan, peel_worklist};
+use history::{PeelHistory, render_layer_list, render_layers, render_remaining};
+use rule::{RemovalRule, parse_neighbourhood};
+use shared::Grid;
+use std::collections::HashSet;
+use std::path::PathBuf;
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Algorithm {
+    // Rescan the whole grid every round until nothing is removed: O(rounds * cells)
+    Rescan,
+    // Keep neighbour counts and only revisit cells next to removed rolls: O(cells)
+    Worklist,
+}
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Topology {
+    // Everything past the edges of the layout is empty space
+    Bounded,
+    // Edges wrap round, so the top row neighbours the bottom row and the left column the right column
+    Toroidal,
+    // Only the rolls are stored, so the floor has no edges (for huge, mostly empty layouts)
+    Sparse,
+}
+// Characters used for rolls and empty spaces in the input. Anything else is rejected.
+#[derive(Clone, Debug, PartialEq)]
+struct Legend {
+    rolls: Vec<char>,
+    empty: Vec<char>,
+}
+impl Default for Legend {
+    fn default() -> Legend {
+        Legend {
+            rolls: vec!['@'],
+            empty: vec!['.'],
+        }
+    }
+}
+impl Legend {
+    fn cell(&self, character: char) -> Option<bool> {
+        if self.rolls.contains(&character) {
+            Some(true)
+        } else if self.empty.contains(&character) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
 fn main() {
-    let layout = match read_input("input") {
-        Err(error) => {
-            println!("Error occured reading day 4 input: {}", error.to_string());
+    let mut algorithm = Algorithm::Rescan;
+    let mut rule = RemovalRule::default();
+    let mut topology = Topology::Bounded;
+    let mut coordinate_input = false;
+    let mut legend = Legend::default();
+    let mut show_layers = false;
+    let mut frame_directory: Option<PathBuf> = None;
+    let mut frame_scale = 4;
+    let mut animation_delay: Option<u64> = None;
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rescan" => algorithm = Algorithm::Rescan,
+            "--worklist" => algorithm = Algorithm::Worklist,
+            "--include-self" => rule.include_self = true,
+            "--topology" => match args.next().as_deref() {
+                Some("bounded") => topology = Topology::Bounded,
+                Some("toroidal") => topology = Topology::Toroidal,
+                Some("sparse") => topology = Topology::Sparse,
+                _ => {
+                    println!("--topology should be bounded, toroidal or sparse");
+                    return;
+                }
+            },
+            // Input is a list of "row,col" roll positions instead of a map
+            "--coordinates" => coordinate_input = true,
+            // Extra characters to accept as rolls or as empty space
+            "--roll-chars" => legend.rolls.extend(args.next().unwrap_or_default().chars()),
+            "--empty-chars" => legend.empty.extend(args.next().unwrap_or_default().chars()),
+            // Print the round each roll is removed in, and the layout left at the end
+            "--layers" => show_layers = true,
+            // Write each round as a PPM image to the given directory, `scale` pixels per cell. The values are
+            // optional, so only take the next argument if it isn't another flag.
+            "--frames" => {
+                let directory = args.next_if(|x| !x.starts_with("--")).unwrap_or(String::from("frames"));
+                frame_directory = Some(PathBuf::from(directory));
+            }
+            "--scale" => {
+                frame_scale = args
+                    .next_if(|x| !x.starts_with("--"))
+                    .and_then(|x| x.parse().ok())
+                    .unwrap_or(frame_scale)
+            }
+            // Play the rounds in the terminal, with an optional delay between frames in ms
+            "--animate" => {
+                let delay = args.next_if(|x| !x.starts_with("--")).and_then(|x| x.parse().ok());
+                animation_delay = Some(delay.unwrap_or(500));
+            }
+            "--threshold" => match args.next().map(|x| x.parse()) {
+                Some(Ok(threshold)) => rule.threshold = threshold,
+                _ => {
+                    println!("--threshold needs a number");
+                    return;
+                }
+            },
+            "--neighbourhood" => match parse_neighbourhood(&args.next().unwrap_or_default()) {
+                Ok(neighbourhood) => rule.neighbourhood = neighbourhood,
+                Err(error) => {
+                    println!("{}", error);
+                    return;
+                }
+            },
+            _ => {
+                println!("Unknown argument: {}", arg);
+                return;
+            }
+        }
+    }
+    if frame_directory.is_some() || animation_delay.is_some() {
+        if coordinate_input || topology == Topology::Sparse {
+            println!("Visualisation needs a bounded or toroidal floor");
             return;
         }
-        Ok(input_data) => input_data,
-    };
-    let (part1, part2) = calculate_answers(layout);
+        let layout = match read_input("input", &legend) {
+            Err(error) => {
+                println!("Error occured reading day 4 input: {}", error);
+                return;
+            }
+            Ok(layout) => layout,
+        };
+        let wrap = topology == Topology::Toroidal;
+        let history = peel(&mut DenseFloor::new(layout.clone(), wrap), algorithm, &rule);
+        let frames = visualise::peel_frames(layout, &history, &rule, wrap);
+        if let Some(directory) = frame_directory {
+            match visualise::write_frames(&frames, &rule, frame_scale, &directory) {
+                Err(error) => println!("Error writing frames: {}", error),
+                Ok(()) => println!("Wrote {} frames to {}", frames.len(), directory.display()),
+            }
+        }
+        if let Some(delay) = animation_delay
+            && let Err(error) = visualise::animate(&frames, &rule, std::time::Duration::from_millis(delay))
+        {
+            println!("Error animating: {}", error);
+        }
+        return;
+    }
+    if coordinate_input {
+        match read_coordinates("input") {
+            Err(error) => println!("Error occured reading day 4 input: {}", error),
+            Ok(rolls) if show_layers => {
+                let history = peel(&mut SparseFloor::new(rolls), algorithm, &rule);
+                print_history(&history, render_layer_list(&history));
+            }
+            Ok(rolls) => print_answers(calculate_answers_sparse(rolls, algorithm, &rule)),
+        }
+    } else {
+        match read_input("input", &legend) {
+            Err(error) => println!("Error occured reading day 4 input: {}", error),
+            Ok(layout) if show_layers && topology == Topology::Sparse => {
+                let history = peel(&mut SparseFloor::new(layout_rolls(&layout)), algorithm, &rule);
+                print_history(&history, render_layer_list(&history));
+            }
+            Ok(layout) if show_layers => {
+                let (width, height) = (layout.width(), layout.height());
+                let wrap = topology == Topology::Toroidal;
+                let history = peel(&mut DenseFloor::new(layout, wrap), algorithm, &rule);
+                let maps = format!(
+                    "{}\nFinal layout:\n{}",
+                    render_layers(&history, width, height),
+                    render_remaining(&history, width, height)
+                );
+                print_history(&history, maps);
+            }
+            Ok(layout) => print_answers(calculate_answers(layout, algorithm, &rule, topology)),
+        }
+    }
+}
+fn print_answers((part1, part2): (i32, i32)) {
     println!("\tDay 4\nPart 1: {}\nPart 2: {}", part1, part2);
 }
-fn calculate_answers(layout: Vec<Vec<bool>>) -> (i32, i32) {
+fn print_history<P>(history: &PeelHistory<P>, rendered: String) {
+    print_answers(history.answers());
+    for (round, num_removed) in history.removed_per_round.iter().enumerate() {
+        println!("Round {}: {} removed", round + 1, num_removed);
+    }
+    println!("Remaining: {}\n{}", history.remaining.len(), rendered);
+}
+fn calculate_answers(layout: Grid<bool>, algorithm: Algorithm, rule: &RemovalRule, topology: Topology) -> (i32, i32) {
+    match (topology, algorithm) {
+        (Topology::Bounded, Algorithm::Rescan) => calculate_answers_rescan(&layout, rule, false),
+        (Topology::Toroidal, Algorithm::Rescan) => calculate_answers_rescan(&layout, rule, true),
+        (Topology::Bounded, Algorithm::Worklist) => {
+            peel(&mut DenseFloor::new(layout, false), algorithm, rule).answers()
+        }
+        (Topology::Toroidal, Algorithm::Worklist) => {
+            peel(&mut DenseFloor::new(layout, true), algorithm, rule).answers()
+        }
+        (Topology::Sparse, _) => calculate_answers_sparse(layout_rolls(&layout), algorithm, rule),
+    }
+}
+fn calculate_answers_sparse(rolls: HashSet<(i64, i64)>, algorithm: Algorithm, rule: &RemovalRule) -> (i32, i32) {
+    peel(&mut SparseFloor::new(rolls), algorithm, rule).answers()
+}
+fn peel<F: Floor>(floor: &mut F, algorithm: Algorithm, rule: &RemovalRule) -> PeelHistory<F::Position> {
+    match algorithm {
+        Algorithm::Rescan => peel_rescan(floor, rule),
+        Algorithm::Worklist => peel_worklist(floor, rule),
+    }
+}
+fn layout_rolls(layout: &Grid<bool>) -> HashSet<(i64, i64)> {
+    layout
+        .iter()
+        .filter(|(_, is_roll)| **is_roll)
+        .map(|((row, col), _)| (row as i64, col as i64))
+        .collect()
+}
+fn calculate_answers_rescan(layout: &Grid<bool>, rule: &RemovalRule, wrap: bool) -> (i32, i32) {
     let mut new_layout;
     let mut num_removed;
-    (new_layout, num_removed) = remove_rolls(layout);
+    (new_layout, num_removed) = remove_rolls(BitGrid::from_grid(layout), rule, wrap);
     let part1 = num_removed;
     let mut part2 = part1;
     while num_removed > 0 {
-        (new_layout, num_removed) = remove_rolls(new_layout);
+        (new_layout, num_removed) = remove_rolls(new_layout, rule, wrap);
         part2 += num_removed;
     }
     (part1, part2)
 }
-fn remove_rolls(layout: Vec<Vec<bool>>) -> (Vec<Vec<bool>>, i32) {
+fn remove_rolls(layout: BitGrid, rule: &RemovalRule, wrap: bool) -> (BitGrid, i32) {
+    // Pick the smallest counter that can hold every count for the neighbourhood
+    match rule.offsets().len() {
+        0..16 => remove_rolls_with_counter::<4>(layout, rule, wrap),
+        16..256 => remove_rolls_with_counter::<8>(layout, rule, wrap),
+        256..65536 => remove_rolls_with_counter::<16>(layout, rule, wrap),
+        _ => remove_rolls_with_counter::<32>(layout, rule, wrap),
+    }
+}
+fn remove_rolls_with_counter<const PLANES: usize>(layout: BitGrid, rule: &RemovalRule, wrap: bool) -> (BitGrid, i32) {
     // Can't modify in place as this is also used for part 1.
-    // Likely would be faster if modified in place instead
-    // (If part 1 was given its own function)
+    // Counts the neighbours of 64 cells at a time by adding shifted copies of the surrounding rows.
     let mut new_layout = layout.clone();
     let mut num_removed = 0;
-    for row in 1..(layout.len() - 1) {
-        for col in 1..(layout[row].len() - 1) {
-            if layout[row][col] {
-                let surroundings = layout[row][col - 1..col + 2]
-                    .iter()
-                    .chain(layout[row - 1][col - 1..col + 2].iter())
-                    .chain(layout[row + 1][col - 1..col + 2].iter())
-                    .filter(|&&x| x)
-                    .count();
-                if surroundings < 5 {
-                    num_removed += 1;
-                    new_layout[row][col] = false;
+    if layout.width() == 0 {
+        return (new_layout, num_removed);
+    }
+    let offsets = rule.offsets();
+    // When wrapping, each row gets extra columns from the other end, so the offsets all move along by that much
+    let halo = match wrap {
+        true => offsets
+            .iter()
+            .map(|(_, col_offset)| col_offset.unsigned_abs())
+            .max()
+            .unwrap_or(0),
+        false => 0,
+    };
+    let wrapped_rows = match wrap {
+        true => (0..layout.height()).map(|row| layout.wrapped_row(row, halo)).collect(),
+        false => Vec::new(),
+    };
+    let neighbour_row = |row: isize| match wrap {
+        true => Some(wrapped_rows[row.rem_euclid(layout.height() as isize) as usize].as_slice()),
+        // Rows outside the grid are empty, so don't add anything
+        false => (row >= 0 && (row as usize) < layout.height()).then(|| layout.row(row as usize)),
+    };
+    // Group the offsets by row, so each neighbouring row is only looked up once per word
+    let mut row_offsets: Vec<(isize, Vec<ColumnShift>)> = Vec::new();
+    for (row_offset, col_offset) in offsets {
+        let col_shift = ColumnShift::new(col_offset + halo as isize);
+        match row_offsets.last_mut() {
+            Some((last_row_offset, col_shifts)) if *last_row_offset == row_offset => col_shifts.push(col_shift),
+            _ => row_offsets.push((row_offset, vec![col_shift])),
+        }
+    }
+    for row in 0..layout.height() {
+        for word_idx in 0..layout.row(row).len() {
+            let mut neighbour_count = SlicedCounter::<PLANES>::new();
+            for (row_offset, col_shifts) in row_offsets.iter() {
+                if let Some(neighbour_row) = neighbour_row(row as isize + row_offset) {
+                    for &col_shift in col_shifts {
+                        neighbour_count.add(shifted_word(neighbour_row, word_idx, col_shift));
+                    }
                 }
             }
+            let removed = layout.row(row)[word_idx] & neighbour_count.below(rule.threshold);
+            num_removed += removed.count_ones() as i32;
+            new_layout.row_mut(row)[word_idx] &= !removed;
         }
     }
     (new_layout, num_removed)
 }
-fn read_input<P>(filename: P) -> Result<Vec<Vec<bool>>, Box<dyn std::error::Error>>
+fn read_input<P>(filename: P, legend: &Legend) -> Result<Grid<bool>, Box<dyn std::error::Error>>
 where
     P: AsRef<std::path::Path>,
 {
-    let mut layout = std::fs::read_to_string(filename)?
-        .trim()
-        .lines()
-        .map(|line| {
-            let mut line_vec = vec![false];
-            line_vec.extend(
-                line.chars()
-                    .map(|character| if character == '@' { true } else { false }),
-            );
-            line_vec.push(false);
-            line_vec
-        })
-        .collect::<Vec<_>>();
-    let line_length = layout[0].len();
-    layout.insert(0, std::iter::repeat(false).take(line_length).collect());
-    layout.push(std::iter::repeat(false).take(line_length).collect());
-    Ok(layout)
+    parse_layout(&std::fs::read_to_string(filename)?, legend)
+}
+// Rolls are true. Anything outside the grid counts as empty space, or wraps round for a toroidal floor.
+fn parse_layout(text: &str, legend: &Legend) -> Result<Grid<bool>, Box<dyn std::error::Error>> {
+    let grid = Grid::from_char_map(text, |character| legend.cell(character))?;
+    if grid.height() == 0 {
+        return Err("Input is empty".into());
+    }
+    Ok(grid)
+}
+
+fn read_coordinates<P>(filename: P) -> Result<HashSet<(i64, i64)>, Box<dyn std::error::Error>>
+where
+    P: AsRef<std::path::Path>,
+{
+    let mut rolls = HashSet::new();
+    for (line_idx, line) in std::fs::read_to_string(filename)?.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let position = line
+            .split_once(',')
+            .and_then(|(row, col)| Some((row.trim().parse().ok()?, col.trim().parse().ok()?)))
+            .ok_or_else(|| {
+                format!(
+                    "Line {} should be a \"row,col\" position, found {:?}",
+                    line_idx + 1,
+                    line
+                )
+            })?;
+        rolls.insert(position);
+    }
+    Ok(rolls)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rule::Neighbourhood;
+    use shared::Lcg;
     #[test]
     fn test_input_parse() {
-        let data = match read_input("test") {
+        let data = match read_input("test", &Legend::default()) {
             Err(error) => {
-                println!("Error reading test input: {}", error.to_string());
+                println!("Error reading test input: {}", error);
                 panic!();
             }
             Ok(data) => data,
         };
-        assert!(data.len() > 2);
-        assert_eq!(data[0], [false; 12]);
-        assert_eq!(data[data.len() - 1], [false; 12]);
+        assert_eq!((data.width(), data.height()), (10, 10));
+        assert_eq!(
+            data.row(0),
+            [false, false, true, true, false, true, true, true, true, false]
+        );
+        assert_eq!(
+            data.row(data.height() - 1),
+            [true, false, true, false, true, true, true, false, true, false]
+        );
+    }
+    #[test]
+    fn test_invalid_input() {
+        let legend = Legend::default();
+        assert_eq!(
+            parse_layout("@.\r\n.@\r\n", &legend).unwrap(),
+            Grid::from_rows(vec![vec![true, false], vec![false, true]]).unwrap()
+        );
+        assert_eq!(
+            parse_layout("@.@\n.@\n", &legend).unwrap_err().to_string(),
+            "Row 2 has length 2, expected 3"
+        );
         assert_eq!(
-            data[1],
-            vec![
-                false, false, false, true, true, false, true, true, true, true, false, false
-            ]
+            parse_layout("@.@\n.x@\n", &legend).unwrap_err().to_string(),
+            "Unexpected character 'x' at row 2, column 2"
         );
+        assert_eq!(parse_layout("", &legend).unwrap_err().to_string(), "Input is empty");
+        assert_eq!(parse_layout("\n\n", &legend).unwrap_err().to_string(), "Input is empty");
+        // A blank line inside the map is an error rather than joining the two halves, trailing ones are fine
         assert_eq!(
-            data[data.len() - 2],
-            vec![
-                false, true, false, true, false, true, true, true, false, true, false, false
-            ]
+            parse_layout("@.@\n\n.@.\n", &legend).unwrap_err().to_string(),
+            "Row 2 is blank"
         );
+        assert_eq!(parse_layout("@.\n.@\n\n\n", &legend).unwrap().height(), 2);
+        // Rows in errors are file lines
+        assert_eq!(
+            parse_layout("@.@\n.@.\n@@@\n.x.\n", &legend).unwrap_err().to_string(),
+            "Unexpected character 'x' at row 4, column 2"
+        );
+        let legend = Legend {
+            rolls: vec!['@', '#'],
+            empty: vec!['.', ' '],
+        };
+        assert_eq!(parse_layout("# \n.@\n", &legend).unwrap().row(0), [true, false]);
     }
     #[test]
     fn test_part_one() {
-        let data = read_input("test").unwrap();
-        let (part1, _part2) = calculate_answers(data);
+        let data = read_input("test", &Legend::default()).unwrap();
+        let (part1, _part2) = calculate_answers(data, Algorithm::Rescan, &RemovalRule::default(), Topology::Bounded);
         assert_eq!(part1, 13);
     }
     #[test]
     fn test_part_two() {
-        let data = read_input("test").unwrap();
-        let (_part1, part2) = calculate_answers(data);
+        let data = read_input("test", &Legend::default()).unwrap();
+        let (_part1, part2) = calculate_answers(data, Algorithm::Rescan, &RemovalRule::default(), Topology::Bounded);
         assert_eq!(part2, 43);
     }
+    fn random_layout(width: usize, height: usize, density: u64) -> Grid<bool> {
+        // Seeded so tests are repeatable
+        let mut rng = Lcg::new(density);
+        Grid::new(width, height, false).map(|_| rng.next_below(100) < density)
+    }
+    #[test]
+    fn test_worklist_matches_rescan() {
+        let data = read_input("test", &Legend::default()).unwrap();
+        assert_eq!(
+            calculate_answers(data, Algorithm::Worklist, &RemovalRule::default(), Topology::Bounded),
+            (13, 43)
+        );
+        // Widths either side of the word size, so rows span several words
+        for (width, density) in [(50, 30), (62, 50), (63, 70), (64, 70), (130, 50), (200, 90)] {
+            let layout = random_layout(width, 50, density);
+            assert_eq!(
+                calculate_answers(
+                    layout.clone(),
+                    Algorithm::Worklist,
+                    &RemovalRule::default(),
+                    Topology::Bounded
+                ),
+                calculate_answers(layout, Algorithm::Rescan, &RemovalRule::default(), Topology::Bounded)
+            );
+        }
+    }
+    #[test]
+    fn test_removal_rules() {
+        // The original rule counted the 3x3 window including the roll itself, and removed it below 5
+        let data = read_input("test", &Legend::default()).unwrap();
+        let including_self = RemovalRule {
+            threshold: 5,
+            include_self: true,
+            ..RemovalRule::default()
+        };
+        assert_eq!(
+            calculate_answers(data, Algorithm::Rescan, &including_self, Topology::Bounded),
+            (13, 43)
+        );
+        let rules = [
+            (Neighbourhood::VonNeumann, 2, false),
+            (Neighbourhood::VonNeumann, 3, true),
+            (Neighbourhood::Square(2), 10, false),
+            (Neighbourhood::Diamond(3), 12, true),
+            (Neighbourhood::Square(4), 40, false),
+        ];
+        for (neighbourhood, threshold, include_self) in rules {
+            let rule = RemovalRule {
+                neighbourhood,
+                threshold,
+                include_self,
+            };
+            for (width, density) in [(20, 50), (130, 70)] {
+                let layout = random_layout(width, 30, density);
+                assert_eq!(
+                    calculate_answers(layout.clone(), Algorithm::Worklist, &rule, Topology::Bounded),
+                    calculate_answers(layout, Algorithm::Rescan, &rule, Topology::Bounded)
+                );
+            }
+        }
+    }
+    #[test]
+    fn test_topologies() {
+        // A full torus has no edges, so every roll has 8 neighbours and nothing can be removed
+        let full = Grid::new(5, 5, true);
+        let rule = RemovalRule::default();
+        for algorithm in [Algorithm::Rescan, Algorithm::Worklist] {
+            assert_eq!(
+                calculate_answers(full.clone(), algorithm, &rule, Topology::Toroidal),
+                (0, 0)
+            );
+            assert_eq!(
+                calculate_answers(full.clone(), algorithm, &rule, Topology::Bounded),
+                (4, 4)
+            );
+        }
+        // Wrapping the test layout round
+        let data = read_input("test", &Legend::default()).unwrap();
+        let toroidal = calculate_answers(data.clone(), Algorithm::Rescan, &rule, Topology::Toroidal);
+        assert_eq!(
+            calculate_answers(data, Algorithm::Worklist, &rule, Topology::Toroidal),
+            toroidal
+        );
+        // Anything outside a bounded layout is empty, so a sparse floor gives the same answers
+        for density in [40, 70] {
+            let layout = random_layout(70, 30, density);
+            let bounded = calculate_answers(layout.clone(), Algorithm::Rescan, &rule, Topology::Bounded);
+            for algorithm in [Algorithm::Rescan, Algorithm::Worklist] {
+                assert_eq!(
+                    calculate_answers(layout.clone(), algorithm, &rule, Topology::Sparse),
+                    bounded
+                );
+                let toroidal = calculate_answers(layout.clone(), Algorithm::Rescan, &rule, Topology::Toroidal);
+                assert_eq!(
+                    calculate_answers(layout.clone(), algorithm, &rule, Topology::Toroidal),
+                    toroidal
+                );
+            }
+        }
+    }
+    #[test]
+    fn test_layers() {
+        let rule = RemovalRule::default();
+        let layout = read_input("test", &Legend::default()).unwrap();
+        let rescan = peel(&mut DenseFloor::new(layout.clone(), false), Algorithm::Rescan, &rule);
+        let mut worklist = peel(&mut DenseFloor::new(layout.clone(), false), Algorithm::Worklist, &rule);
+        assert_eq!(rescan.answers(), (13, 43));
+        assert_eq!(rescan.removed_per_round, vec![13, 12, 7, 5, 2, 1, 1, 1, 1]);
+        assert_eq!(rescan.remaining.len(), 71 - 43);
+        // Same layers, but the worklist goes through each round in a different order
+        worklist.layers.sort();
+        worklist.remaining.sort();
+        let mut rescan = rescan;
+        rescan.layers.sort();
+        assert_eq!(worklist, rescan);
+        assert_eq!(render_layers(&rescan, 10, 10).lines().next(), Some("..11.1121."));
+        let sparse = peel(&mut SparseFloor::new(layout_rolls(&layout)), Algorithm::Worklist, &rule);
+        assert_eq!(sparse.removed_per_round, rescan.removed_per_round);
+    }
+    #[test]
+    fn test_frames() {
+        let rule = RemovalRule::default();
+        let layout = read_input("test", &Legend::default()).unwrap();
+        let history = peel(&mut DenseFloor::new(layout.clone(), false), Algorithm::Worklist, &rule);
+        let frames = visualise::peel_frames(layout, &history, &rule, false);
+        assert_eq!(frames.len(), history.removed_per_round.len() + 1);
+        // Each frame only loses the rolls removed in that round
+        for (frame, &num_removed) in frames.iter().zip(history.removed_per_round.iter()) {
+            assert_eq!(
+                frame.iter().filter(|(_, cell)| cell.is_some_and(|x| x < 4)).count(),
+                num_removed
+            );
+        }
+        assert_eq!(frames[0][(0, 2)], Some(3));
+        assert_eq!(frames[0][(0, 0)], None);
+        let directory = std::env::temp_dir().join(format!("day4_frames_{}", std::process::id()));
+        visualise::write_frames(&frames, &rule, 2, &directory).unwrap();
+        let first_frame = std::fs::read(directory.join("frame_0000.ppm")).unwrap();
+        assert!(first_frame.starts_with(b"P6\n20 20\n255\n"));
+        assert_eq!(first_frame.len(), b"P6\n20 20\n255\n".len() + 20 * 20 * 3);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+    #[test]
+    fn test_small_torus() {
+        // Neighbourhood is wider than the torus, so some offsets wrap round to the same cell more than once
+        let rule = RemovalRule {
+            neighbourhood: Neighbourhood::Square(3),
+            threshold: 30,
+            include_self: false,
+        };
+        for density in [50, 70, 90] {
+            let layout = random_layout(5, 4, density);
+            assert_eq!(
+                calculate_answers(layout.clone(), Algorithm::Worklist, &rule, Topology::Toroidal),
+                calculate_answers(layout, Algorithm::Rescan, &rule, Topology::Toroidal)
+            );
+        }
+    }
+    #[test]
+    fn test_sparse_floor() {
+        // Two squares of 4 rolls, far enough apart that they couldn't be stored as a grid
+        let rolls = [
+            (0, 0),
+            (0, 1),
+            (1, 0),
+            (1, 1),
+            (4_000_000_000, -4_000_000_000),
+            (4_000_000_000, -3_999_999_999),
+            (4_000_000_001, -4_000_000_000),
+            (4_000_000_001, -3_999_999_999),
+        ]
+        .into_iter()
+        .collect::<HashSet<_>>();
+        let rule = RemovalRule::default();
+        for algorithm in [Algorithm::Rescan, Algorithm::Worklist] {
+            assert_eq!(calculate_answers_sparse(rolls.clone(), algorithm, &rule), (8, 8));
+        }
+        // Rolls on the edges of the i64 range have neighbours that can't be represented
+        let rolls = [(i64::MAX, 0), (i64::MAX, 1), (i64::MIN, i64::MIN), (0, i64::MAX)]
+            .into_iter()
+            .collect::<HashSet<_>>();
+        let rule = RemovalRule {
+            neighbourhood: Neighbourhood::Square(2),
+            ..RemovalRule::default()
+        };
+        for algorithm in [Algorithm::Rescan, Algorithm::Worklist] {
+            assert_eq!(calculate_answers_sparse(rolls.clone(), algorithm, &rule), (4, 4));
+        }
+    }
 }
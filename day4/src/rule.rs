@@ -0,0 +1,96 @@
+use std::error::Error;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Neighbourhood {
+    // The 8 surrounding cells
+    Moore,
+    // The 4 orthogonally adjacent cells
+    VonNeumann,
+    // Every cell within `radius` steps, diagonals included (Moore is a square of radius 1)
+    Square(usize),
+    // Every cell within `radius` orthogonal steps (Von Neumann is a diamond of radius 1)
+    Diamond(usize),
+}
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RemovalRule {
+    pub neighbourhood: Neighbourhood,
+    // A roll is removed when fewer than this many rolls are in its neighbourhood
+    pub threshold: usize,
+    // Whether the roll itself counts towards the threshold
+    pub include_self: bool,
+}
+impl Default for RemovalRule {
+    // Puzzle rule, a roll can be reached if fewer than 4 of the 8 surrounding cells hold rolls
+    fn default() -> RemovalRule {
+        RemovalRule {
+            neighbourhood: Neighbourhood::Moore,
+            threshold: 4,
+            include_self: false,
+        }
+    }
+}
+impl RemovalRule {
+    // (row, col) offsets of every cell in the neighbourhood, the cell itself is only included if it counts.
+    // Every neighbourhood is symmetric, so these are also the cells that have this cell as a neighbour.
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        let (radius, in_neighbourhood): (usize, fn(isize, isize, isize) -> bool) = match self.neighbourhood {
+            Neighbourhood::Moore => (1, |_, _, _| true),
+            Neighbourhood::VonNeumann => (1, |row, col, radius| row.abs() + col.abs() <= radius),
+            Neighbourhood::Square(radius) => (radius, |_, _, _| true),
+            Neighbourhood::Diamond(radius) => (radius, |row, col, radius| row.abs() + col.abs() <= radius),
+        };
+        let radius = radius as isize;
+        let mut offsets = Vec::new();
+        for row in -radius..=radius {
+            for col in -radius..=radius {
+                if in_neighbourhood(row, col, radius) && (self.include_self || (row, col) != (0, 0)) {
+                    offsets.push((row, col));
+                }
+            }
+        }
+        offsets
+    }
+}
+
+pub fn parse_neighbourhood(text: &str) -> Result<Neighbourhood, Box<dyn Error>> {
+    // Either "moore", "von-neumann", "square:<radius>" or "diamond:<radius>"
+    let (name, radius) = match text.split_once(':') {
+        Some((name, radius)) => (name, Some(radius.parse::<usize>()?)),
+        None => (text, None),
+    };
+    match (name, radius) {
+        ("moore", None) => Ok(Neighbourhood::Moore),
+        ("von-neumann", None) => Ok(Neighbourhood::VonNeumann),
+        ("square", Some(radius)) => Ok(Neighbourhood::Square(radius)),
+        ("diamond", Some(radius)) => Ok(Neighbourhood::Diamond(radius)),
+        _ => Err(format!("Unknown neighbourhood {:?}", text).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_offsets() {
+        let mut rule = RemovalRule::default();
+        assert_eq!(rule.offsets().len(), 8);
+        rule.include_self = true;
+        assert_eq!(rule.offsets().len(), 9);
+        rule.neighbourhood = Neighbourhood::VonNeumann;
+        assert_eq!(rule.offsets(), vec![(-1, 0), (0, -1), (0, 0), (0, 1), (1, 0)]);
+        rule.include_self = false;
+        rule.neighbourhood = Neighbourhood::Square(2);
+        assert_eq!(rule.offsets().len(), 24);
+        rule.neighbourhood = Neighbourhood::Diamond(2);
+        assert_eq!(rule.offsets().len(), 12);
+    }
+    #[test]
+    fn test_parse_neighbourhood() {
+        assert_eq!(parse_neighbourhood("moore").unwrap(), Neighbourhood::Moore);
+        assert_eq!(parse_neighbourhood("von-neumann").unwrap(), Neighbourhood::VonNeumann);
+        assert_eq!(parse_neighbourhood("square:3").unwrap(), Neighbourhood::Square(3));
+        assert_eq!(parse_neighbourhood("diamond:2").unwrap(), Neighbourhood::Diamond(2));
+        assert!(parse_neighbourhood("square").is_err());
+        assert!(parse_neighbourhood("hexagon:1").is_err());
+    }
+}
@@ -0,0 +1,132 @@
+use shared::Grid;
+use std::io::Write;
+use std::path::Path;
+
+use crate::floor::{DenseFloor, Floor};
+use crate::history::PeelHistory;
+use crate::rule::RemovalRule;
+
+// Each frame is the floor at the start of a round, with the neighbour count of every roll (None for no roll)
+pub type Frame = Grid<Option<usize>>;
+
+// One frame for the start of each round, plus a frame for the final stable layout
+pub fn peel_frames(
+    rolls: Grid<bool>,
+    history: &PeelHistory<(usize, usize)>,
+    rule: &RemovalRule,
+    wrap: bool,
+) -> Vec<Frame> {
+    let (width, height) = (rolls.width(), rolls.height());
+    let offsets = rule.offsets();
+    let mut neighbours = Vec::with_capacity(offsets.len());
+    let mut floor = DenseFloor::new(rolls, wrap);
+    let mut removed_by_round = vec![Vec::new(); history.removed_per_round.len()];
+    for &(position, layer) in history.layers.iter() {
+        removed_by_round[layer - 1].push(position);
+    }
+    let mut frames = Vec::with_capacity(removed_by_round.len() + 1);
+    for removed in removed_by_round.iter().map(Some).chain(std::iter::once(None)) {
+        let mut frame = Grid::new(width, height, None);
+        for roll in floor.rolls() {
+            frame[roll] = Some(floor.count_neighbours(roll, rule, &offsets, &mut neighbours));
+        }
+        frames.push(frame);
+        removed.into_iter().flatten().for_each(|&roll| floor.remove(roll));
+    }
+    frames
+}
+
+// Rolls that are about to be removed go from red to orange, the rest from yellow to green as they get more
+// crowded. Empty cells are dark grey.
+pub fn cell_colour(cell: Option<usize>, rule: &RemovalRule) -> [u8; 3] {
+    let Some(neighbour_count) = cell else {
+        return [40, 40, 40];
+    };
+    if neighbour_count < rule.threshold {
+        let shade = (neighbour_count * 160 / rule.threshold.max(1)) as u8;
+        [255, 40 + shade, 40]
+    } else {
+        let max_count = rule.offsets().len().max(rule.threshold + 1);
+        let shade = ((neighbour_count - rule.threshold) * 200 / (max_count - rule.threshold)) as u8;
+        [230 - shade, 220, 60]
+    }
+}
+
+// Binary PPM (P6), with each cell drawn as a `scale` x `scale` square
+pub fn write_ppm(frame: &Frame, rule: &RemovalRule, scale: usize, path: &Path) -> std::io::Result<()> {
+    let (width, height) = (frame.width() * scale, frame.height() * scale);
+    let mut data = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    data.reserve(width * height * 3);
+    for row in frame.rows() {
+        let pixel_row = row
+            .iter()
+            .flat_map(|&cell| std::iter::repeat_n(cell_colour(cell, rule), scale))
+            .flatten()
+            .collect::<Vec<u8>>();
+        for _ in 0..scale {
+            data.extend_from_slice(&pixel_row);
+        }
+    }
+    std::fs::write(path, data)
+}
+
+pub fn write_frames(frames: &[Frame], rule: &RemovalRule, scale: usize, directory: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(directory)?;
+    for (idx, frame) in frames.iter().enumerate() {
+        write_ppm(frame, rule, scale, &directory.join(format!("frame_{:04}.ppm", idx)))?;
+    }
+    Ok(())
+}
+
+// Frame drawn with 24-bit ANSI colours, rolls as '@' and empty cells as '.'
+pub fn render_ansi(frame: &Frame, rule: &RemovalRule) -> String {
+    let mut text = String::new();
+    for row in frame.rows() {
+        for &cell in row {
+            let [red, green, blue] = cell_colour(cell, rule);
+            let character = if cell.is_some() { '@' } else { '.' };
+            text.push_str(&format!("\x1b[38;2;{};{};{}m{}", red, green, blue, character));
+        }
+        text.push_str("\x1b[0m\n");
+    }
+    text
+}
+
+pub fn animate(frames: &[Frame], rule: &RemovalRule, delay: std::time::Duration) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout().lock();
+    for (idx, frame) in frames.iter().enumerate() {
+        // Move to the top left and clear the screen before drawing each frame
+        writeln!(
+            stdout,
+            "\x1b[H\x1b[2J{}Round {}/{}",
+            render_ansi(frame, rule),
+            idx,
+            frames.len() - 1
+        )?;
+        stdout.flush()?;
+        std::thread::sleep(delay);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_colours() {
+        let rule = RemovalRule::default();
+        assert_eq!(cell_colour(None, &rule), [40, 40, 40]);
+        assert_eq!(cell_colour(Some(0), &rule), [255, 40, 40]);
+        assert_eq!(cell_colour(Some(4), &rule), [230, 220, 60]);
+        assert_eq!(cell_colour(Some(8), &rule), [30, 220, 60]);
+    }
+    #[test]
+    fn test_render_ansi() {
+        let mut frame = Grid::new(2, 1, None);
+        frame[(0, 1)] = Some(0);
+        assert_eq!(
+            render_ansi(&frame, &RemovalRule::default()),
+            "\x1b[38;2;40;40;40m.\x1b[38;2;255;40;40m@\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::Interval;
+
+// Which of the original (unmerged) ranges each ingredient falls in, and how many ingredients each range covers
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+    // Indices into the original ranges, one list per ingredient in input order
+    pub matched_ranges: Vec<Vec<usize>>,
+    // Number of ingredient IDs in each original range, including ranges that cover nothing
+    pub range_counts: Vec<u128>,
+}
+
+// Single IDs are sorted once, then each range finds the IDs it covers with two binary searches:
+// O((ranges + ingredients) log ingredients + matches). Ingredient ranges are checked against every range.
+pub fn explain(intervals: &[Interval], ingredients: &[Interval]) -> Explanation {
+    let (mut single_ids, id_ranges): (Vec<usize>, Vec<usize>) =
+        (0..ingredients.len()).partition(|&idx| ingredients[idx].0 == ingredients[idx].1);
+    single_ids.sort_unstable_by_key(|&idx| ingredients[idx].0);
+    let mut matched_ranges = vec![Vec::new(); ingredients.len()];
+    let mut range_counts = Vec::with_capacity(intervals.len());
+    for (range_idx, &(start, end)) in intervals.iter().enumerate() {
+        let first = single_ids.partition_point(|&idx| ingredients[idx].0 < start);
+        let last = single_ids.partition_point(|&idx| ingredients[idx].0 <= end);
+        let covered = single_ids.get(first..last).unwrap_or_default();
+        covered.iter().for_each(|&idx| matched_ranges[idx].push(range_idx));
+        let mut count = covered.len() as u128;
+        for &idx in id_ranges.iter() {
+            let overlap = (ingredients[idx].0.max(start), ingredients[idx].1.min(end));
+            if overlap.0 <= overlap.1 {
+                matched_ranges[idx].push(range_idx);
+                count += (overlap.1 as i128 - overlap.0 as i128 + 1) as u128;
+            }
+        }
+        range_counts.push(count);
+    }
+    Explanation {
+        matched_ranges,
+        range_counts,
+    }
+}
+
+pub fn render_explanation(intervals: &[Interval], ingredients: &[Interval], explanation: &Explanation) -> String {
+    let mut text = String::new();
+    for (&(first_id, last_id), ranges) in ingredients.iter().zip(explanation.matched_ranges.iter()) {
+        let ranges = if ranges.is_empty() {
+            String::from("spoiled")
+        } else {
+            ranges
+                .iter()
+                .map(|&idx| format!("{}-{}", intervals[idx].0, intervals[idx].1))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        if first_id == last_id {
+            text.push_str(&format!("Ingredient {}: {}\n", first_id, ranges));
+        } else {
+            text.push_str(&format!("Ingredients {}-{}: {}\n", first_id, last_id, ranges));
+        }
+    }
+    for (&(start, end), count) in intervals.iter().zip(explanation.range_counts.iter()) {
+        text.push_str(&format!("Range {}-{}: {} ingredients\n", start, end, count));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_explain() {
+        let intervals = [(3, 5), (10, 14), (16, 20), (12, 18), (30, 31)];
+        let ingredients = [1, 5, 8, 11, 17, 32, 17].map(|id| (id, id));
+        let explanation = explain(&intervals, &ingredients);
+        assert_eq!(
+            explanation.matched_ranges,
+            vec![vec![], vec![0], vec![], vec![1], vec![2, 3], vec![], vec![2, 3]]
+        );
+        assert_eq!(explanation.range_counts, vec![1, 1, 2, 2, 0]);
+        let text = render_explanation(&intervals, &ingredients, &explanation);
+        assert!(text.starts_with("Ingredient 1: spoiled\nIngredient 5: 3-5\n"));
+        assert!(text.contains("Ingredient 17: 16-20, 12-18\n"));
+        assert!(text.ends_with("Range 12-18: 2 ingredients\nRange 30-31: 0 ingredients\n"));
+        let ingredients = [(4, 11), (17, 17)];
+        let explanation = explain(&intervals, &ingredients);
+        assert_eq!(explanation.matched_ranges, vec![vec![0, 1], vec![2, 3]]);
+        assert_eq!(explanation.range_counts, vec![2, 2, 1, 1, 0]);
+        let text = render_explanation(&intervals, &ingredients, &explanation);
+        assert!(text.starts_with("Ingredients 4-11: 3-5, 10-14\n"));
+    }
+}
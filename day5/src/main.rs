@@ -1,123 +1,273 @@
-use shared;
+mod explain;
+mod server;
+use explain::{explain, render_explanation};
+use shared::IntervalSet;
 use std::error::Error;
-use std::fs::File;
-use std::io::{BufReader, Lines};
-use std::num::ParseIntError;
+use std::sync::Mutex;
 
 fn main() {
+    let mut show_explanation = false;
+    let mut serve_stdin = false;
+    let mut socket_path: Option<String> = None;
+    let mut args = std::env::args().skip(1).peekable();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            // List the original ranges matching each ingredient, and the ingredients in each range
+            "--explain" => show_explanation = true,
+            // Keep the ranges loaded and answer queries, one per line, from stdin or a Unix socket. The socket path
+            // is optional, so the next argument is only taken if it isn't another flag.
+            "--serve" => serve_stdin = true,
+            "--socket" => {
+                let path = args.next_if(|x| !x.starts_with("--"));
+                socket_path = Some(path.unwrap_or(String::from("day5.sock")));
+            }
+            _ => {
+                println!("Unknown argument: {}", arg);
+                return;
+            }
+        }
+    }
     let (intervals, ingredients) = match read_input("input") {
         Err(error) => {
-            println!("Error occured reading day 5 input: {}", error.to_string());
+            println!("Error occured reading day 5 input: {}", error);
             return;
         }
         Ok(input_data) => input_data,
     };
+    if serve_stdin || socket_path.is_some() {
+        let fresh = IntervalSet::from_iter(intervals);
+        let result = match socket_path {
+            Some(path) => server::serve_socket(fresh, path),
+            None => server::serve(&Mutex::new(fresh), std::io::stdin().lock(), std::io::stdout()).map(drop),
+        };
+        if let Err(error) = result {
+            println!("Error serving day 5 queries: {}", error);
+        }
+        return;
+    }
+    if show_explanation {
+        let explanation = explain(&intervals, &ingredients);
+        print!("{}", render_explanation(&intervals, &ingredients, &explanation));
+        return;
+    }
     let (part1, part2) = calculate_answers(intervals, ingredients);
     println!("\tDay 5\nPart 1: {}\nPart 2: {}", part1, part2);
 }
 
-fn calculate_answers(intervals: Vec<(i64, i64)>, ingredients: Vec<i64>) -> (i64, i64) {
-    let intervals = merge_all_intervals(intervals);
-    let mut part1 = 0;
-    for ingredient in ingredients {
-        for interval in intervals.iter() {
-            if item_in_interval(ingredient, *interval) {
-                part1 += 1;
-                break;
-            }
-        }
-    }
-    let part2 = intervals.iter().map(|x| x.1 - x.0 + 1).sum();
-    (part1, part2)
-}
+// Inclusive range of ingredient IDs
+type Interval = (i64, i64);
 
-fn merge_all_intervals(intervals: Vec<(i64, i64)>) -> Vec<(i64, i64)> {
-    let mut old_intervals: Vec<(i64, i64)> = vec![];
-    let mut new_intervals: Vec<(i64, i64)> = vec![];
-    for mut interval in intervals {
-        std::mem::swap(&mut new_intervals, &mut old_intervals);
-        new_intervals.clear();
-        for old_interval in old_intervals.iter() {
-            if intervals_overlap(interval, *old_interval) {
-                // Intervals overlap, merge them and don't push yet.
-                interval = merge_two_intervals(interval, *old_interval)
-            } else {
-                // Intervals don't overlap, so leave old_interval unmodified
-                new_intervals.push(*old_interval);
-            }
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Section {
+    // Ranges of fresh ingredient IDs
+    Ranges,
+    // Available ingredient IDs, or ranges of them
+    Ingredients,
+}
+impl Section {
+    fn name(&self) -> &'static str {
+        match self {
+            Section::Ranges => "ranges",
+            Section::Ingredients => "ingredients",
         }
-        new_intervals.push(interval);
     }
-    new_intervals
-}
-
-fn intervals_overlap(interval1: (i64, i64), interval2: (i64, i64)) -> bool {
-    return item_in_interval(interval1.0, interval2)
-        || item_in_interval(interval1.1, interval2)
-        || item_in_interval(interval2.0, interval1)
-        || item_in_interval(interval2.1, interval1)
 }
 
-fn merge_two_intervals(interval1: (i64, i64), interval2: (i64, i64)) -> (i64, i64) {
-    return (
-        std::cmp::min(interval1.0, interval2.0),
-        std::cmp::max(interval1.1, interval2.1),
-    );
+// Part 1 counts every fresh ID within the ingredient ranges, so a single ID counts once
+fn calculate_answers(intervals: Vec<Interval>, ingredients: Vec<Interval>) -> (u128, u128) {
+    let fresh = IntervalSet::from_iter(intervals);
+    let part1 = ingredients
+        .into_iter()
+        .map(|ingredient| fresh.count_within(ingredient))
+        .sum();
+    (part1, fresh.total_length())
 }
-fn item_in_interval(item: i64, interval: (i64, i64)) -> bool { item >= interval.0 && item <= interval.1 }
 
-fn read_input<P>(filename: P) -> Result<(Vec<(i64, i64)>, Vec<i64>), Box<dyn Error>>
+fn read_input<P>(filename: P) -> Result<(Vec<Interval>, Vec<Interval>), Box<dyn Error>>
 where
     P: AsRef<std::path::Path>,
 {
-    let mut line_iterator = shared::get_lines_from_file(filename)?;
-    // Take a mut_ref so take_while can be used twice
-    let line_iterator_mutref: &mut Lines<BufReader<File>> = &mut line_iterator;
-    let intervals: Vec<(i64, i64)> = line_iterator_mutref
-        .take_while(|x| x.is_ok() && x.as_ref().unwrap().len() > 0)
-        .map(Result::unwrap)
-        .map(parse_interval)
-        .collect::<Result<Vec<(i64, i64)>, Box<dyn Error>>>()?;
-    let ingredients = line_iterator_mutref
-        .take_while(|x| x.as_ref().is_ok() && x.as_ref().unwrap().len() > 0)
-        .map(Result::unwrap)
-        .map(|x| x.as_str().parse())
-        .collect::<Result<Vec<i64>, ParseIntError>>()?;
+    parse_input(&std::fs::read_to_string(filename)?)
+}
+
+// Fresh ranges then ingredients, either separated by blank lines or introduced by "[ranges]" and "[ingredients]"
+// headers. Lines starting with '#' are comments. Once a header has been seen, blank lines no longer change section.
+fn parse_input(text: &str) -> Result<(Vec<Interval>, Vec<Interval>), Box<dyn Error>> {
+    let mut section = Section::Ranges;
+    let mut has_headers = false;
+    let mut intervals = Vec::new();
+    let mut ingredients = Vec::new();
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.is_empty() {
+            if !has_headers && !intervals.is_empty() {
+                section = Section::Ingredients;
+            }
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            section = match header.trim().to_ascii_lowercase().as_str() {
+                "ranges" => Section::Ranges,
+                "ingredients" => Section::Ingredients,
+                _ => return Err(format!("Line {}: unknown section header {:?}", line_idx + 1, line).into()),
+            };
+            has_headers = true;
+            continue;
+        }
+        let parsed = match section {
+            Section::Ranges => parse_interval(line).map(|interval| intervals.push(interval)),
+            Section::Ingredients => parse_ingredient(line).map(|ingredient| ingredients.push(ingredient)),
+        };
+        parsed.map_err(|error| format!("Line {} ({} section): {}", line_idx + 1, section.name(), error))?;
+    }
     Ok((intervals, ingredients))
 }
 
-fn parse_interval(interval_string: String) -> Result<(i64, i64), Box<dyn Error>> {
-    let interval_vec = interval_string
-        .split('-')
-        .map(str::parse)
-        .collect::<Result<Vec<i64>, ParseIntError>>()?;
-    if interval_vec.len() != 2 {
-        return Err("Invalid interval format in input file".into());
-    }
-    // Convert vec to tuple
-    Ok((interval_vec[0], interval_vec[1]))
+// Single ID (which can be negative, like "-7"), or a range of IDs in the same format as the fresh ranges.
+// Something like "-7" is always the ID rather than an open range.
+fn parse_ingredient(ingredient_string: &str) -> Result<Interval, Box<dyn Error>> {
+    match ingredient_string.parse() {
+        Ok(id) => Ok((id, id)),
+        Err(_) if ingredient_string.contains('-') => parse_interval(ingredient_string),
+        Err(error) => Err(error.into()),
+    }
+}
+
+// "start-end", where either end can be left out for an open range ("5-", "-100" or "-"). Bounds can be negative
+// ("-5-10", "3--2"), the separator is the first '-' that comes after a digit.
+fn parse_interval(interval_string: &str) -> Result<Interval, Box<dyn Error>> {
+    let (start, end) = split_interval(interval_string)
+        .ok_or_else(|| format!("Invalid interval {:?}, expected \"start-end\"", interval_string))?;
+    let parse_bound = |bound: &str, open: i64| match bound.trim() {
+        "" => Ok(open),
+        bound => bound.parse::<i64>(),
+    };
+    let interval = (parse_bound(start, i64::MIN)?, parse_bound(end, i64::MAX)?);
+    if interval.0 > interval.1 {
+        return Err(format!("Invalid interval {:?}, start is after end", interval_string).into());
+    }
+    Ok(interval)
+}
+fn split_interval(interval_string: &str) -> Option<(&str, &str)> {
+    let mut previous = None;
+    for (idx, character) in interval_string.char_indices() {
+        if character == '-' && previous.is_some_and(|x: char| x.is_ascii_digit()) {
+            return Some((&interval_string[..idx], &interval_string[idx + 1..]));
+        }
+        if !character.is_whitespace() {
+            previous = Some(character);
+        }
+    }
+    // No separator after a start, so this can only be an open start like "-100"
+    interval_string.trim_start().strip_prefix('-').map(|end| ("", end))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use shared::Lcg;
     #[test]
     fn test_input_parse() {
         let (intervals, ingredients) = match read_input("test") {
             Err(error) => {
-                println!("Error reading test data: {}", error.to_string());
+                println!("Error reading test data: {}", error);
                 panic!();
             }
             Ok(input_data) => input_data,
         };
         assert_eq!(intervals, vec![(3, 5), (10, 14), (16, 20), (12, 18)]);
-        assert_eq!(ingredients, vec![1, 5, 8, 11, 17, 32]);
+        assert_eq!(ingredients, vec![(1, 1), (5, 5), (8, 8), (11, 11), (17, 17), (32, 32)]);
     }
     #[test]
     fn test_combine_intervals() {
         let (intervals, _ingredients) = read_input("test").unwrap();
-        let new_intervals = merge_all_intervals(intervals);
-        assert_eq!(new_intervals, vec![(3, 5), (10, 20)]);
+        let fresh = IntervalSet::from_iter(intervals);
+        assert_eq!(fresh.iter().collect::<Vec<_>>(), vec![(3, 5), (10, 20)]);
+    }
+    #[test]
+    fn test_merge_matches_brute_force() {
+        // Seeded so the test is repeatable
+        let mut rng = Lcg::new(5);
+        let mut next = |limit: u64| rng.next_below(limit) as i64;
+        let intervals = (0..200)
+            .map(|_| {
+                let start = next(10_000);
+                (start, start + next(100))
+            })
+            .collect::<Vec<_>>();
+        let fresh = IntervalSet::from_iter(intervals.clone());
+        assert!(fresh.gaps().all(|gap| gap.0 <= gap.1));
+        for item in (0..1_000).map(|_| next(10_200)).chain([-1, 0, 10_100, i64::MAX]) {
+            let expected = intervals.iter().any(|&(start, end)| item >= start && item <= end);
+            assert_eq!(fresh.contains(item), expected, "item {}", item);
+        }
+        // Touching ranges merge as well as overlapping ones
+        let fresh = IntervalSet::from_iter([(5, 9), (1, 3), (3, 4), (9, 9)]);
+        assert_eq!(fresh.iter().collect::<Vec<_>>(), vec![(1, 9)]);
+        assert!(!IntervalSet::new().contains(3));
+    }
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("3-5").unwrap(), (3, 5));
+        assert_eq!(parse_interval("5-").unwrap(), (5, i64::MAX));
+        assert_eq!(parse_interval("-100").unwrap(), (i64::MIN, 100));
+        assert_eq!(parse_interval("-").unwrap(), (i64::MIN, i64::MAX));
+        assert_eq!(
+            parse_interval("9-3").unwrap_err().to_string(),
+            "Invalid interval \"9-3\", start is after end"
+        );
+        assert!(parse_interval("9").is_err());
+        assert!(parse_interval("1-2-3").is_err());
+        assert_eq!(parse_interval("-5-10").unwrap(), (-5, 10));
+        assert_eq!(
+            parse_interval("3--2").unwrap_err().to_string(),
+            "Invalid interval \"3--2\", start is after end"
+        );
+        assert_eq!(parse_interval("-30--2").unwrap(), (-30, -2));
+        assert_eq!(parse_interval("-5-").unwrap(), (-5, i64::MAX));
+        assert_eq!(parse_interval("--5").unwrap(), (i64::MIN, -5));
+        assert_eq!(parse_interval(" -5 - 10 ").unwrap(), (-5, 10));
+        let (part1, part2) = calculate_answers(vec![(10, 20), (15, i64::MAX), (i64::MIN, 0)], vec![(-7, -7), (5, 12)]);
+        assert_eq!((part1, part2), (4, (1 << 64) - 9));
+    }
+    #[test]
+    fn test_negative_ingredients() {
+        let (intervals, ingredients) = parse_input("-10-10\n\n-7\n-3--1\n-2\n5-\n").unwrap();
+        assert_eq!(intervals, vec![(-10, 10)]);
+        assert_eq!(ingredients, vec![(-7, -7), (-3, -1), (-2, -2), (5, i64::MAX)]);
+        let (intervals, ingredients) = parse_input("3-5\n\n-7\n").unwrap();
+        assert_eq!(ingredients, vec![(-7, -7)]);
+        assert_eq!(calculate_answers(vec![(-10, 10)], ingredients).0, 1);
+        assert_eq!(calculate_answers(intervals, vec![]).0, 0);
+    }
+    #[test]
+    fn test_tolerant_input() {
+        let text = "# Fresh ranges\r\n3-5\r\n10-14\r\n\r\n\r\n# Ingredients\r\n1\r\n\r\n4-11\r\n17\r\n";
+        let (intervals, ingredients) = parse_input(text).unwrap();
+        assert_eq!(intervals, vec![(3, 5), (10, 14)]);
+        assert_eq!(ingredients, vec![(1, 1), (4, 11), (17, 17)]);
+        assert_eq!(calculate_answers(intervals, ingredients), (4, 8));
+        let text = "[Ingredients]\n5\n\n[ranges]\n3-5\n\n10-14\n[ingredients]\n11\n";
+        let (intervals, ingredients) = parse_input(text).unwrap();
+        assert_eq!(intervals, vec![(3, 5), (10, 14)]);
+        assert_eq!(ingredients, vec![(5, 5), (11, 11)]);
+        assert_eq!(
+            parse_input("3-5\n\n7\nseven\n").unwrap_err().to_string(),
+            "Line 4 (ingredients section): invalid digit found in string"
+        );
+        assert_eq!(
+            parse_input("3-5\n9-3\n").unwrap_err().to_string(),
+            "Line 2 (ranges section): Invalid interval \"9-3\", start is after end"
+        );
+        assert!(
+            parse_input("[spoiled]\n")
+                .unwrap_err()
+                .to_string()
+                .starts_with("Line 1: unknown section")
+        );
     }
     #[test]
     fn test_part_one() {
@@ -0,0 +1,217 @@
+use shared::IntervalSet;
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::parse_interval;
+
+const HELP: &str =
+    "Commands: check ID, count START-END, add START-END, remove START-END, total, ranges, help, quit, shutdown";
+
+// How a session ended: the client went away (or sent "quit"), or asked the whole server to stop
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionEnd {
+    Closed,
+    Shutdown,
+}
+
+// One line per command, one line per reply. Errors are replied as "error: ..." and the session carries on.
+// The set is only locked while a command runs, so other sessions can use it in between.
+pub fn serve<R: BufRead, W: Write>(fresh: &Mutex<IntervalSet>, input: R, mut output: W) -> std::io::Result<SessionEnd> {
+    for line in input.lines() {
+        let line = line?;
+        let reply = match line.trim() {
+            "" => continue,
+            "quit" => break,
+            "shutdown" => return Ok(SessionEnd::Shutdown),
+            command => {
+                let mut fresh = fresh.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                handle_command(&mut fresh, command).unwrap_or_else(|error| format!("error: {}", error))
+            }
+        };
+        writeln!(output, "{}", reply)?;
+        output.flush()?;
+    }
+    Ok(SessionEnd::Closed)
+}
+
+// Each client gets its own thread, so an idle client doesn't hold up the others, and additions and removals
+// are seen by every client straight away. Runs until a client sends "shutdown", then removes the socket file.
+// A socket left behind by a server that was killed is replaced on the next start, but one that a server is
+// still listening on is an error.
+pub fn serve_socket<P: AsRef<Path>>(fresh: IntervalSet, path: P) -> std::io::Result<()> {
+    let path = path.as_ref().to_path_buf();
+    remove_stale_socket(&path)?;
+    let listener = UnixListener::bind(&path)?;
+    let _socket_file = SocketFile::new(path.clone())?;
+    let fresh = Arc::new(Mutex::new(fresh));
+    let stopping = Arc::new(AtomicBool::new(false));
+    for stream in listener.incoming() {
+        if stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(error) => {
+                println!("Failed to accept connection: {}", error);
+                continue;
+            }
+        };
+        let (fresh, stopping, path) = (Arc::clone(&fresh), Arc::clone(&stopping), path.clone());
+        std::thread::spawn(move || match serve(&fresh, BufReader::new(&stream), &stream) {
+            Ok(SessionEnd::Closed) => (),
+            Ok(SessionEnd::Shutdown) => {
+                stopping.store(true, Ordering::SeqCst);
+                // Wake the listener up so it sees the flag
+                let _ = UnixStream::connect(&path);
+            }
+            Err(error) => println!("Connection closed: {}", error),
+        });
+    }
+    Ok(())
+}
+
+// Only ever removes a socket that nothing is listening on, anything else at the path is left alone and binding
+// fails
+fn remove_stale_socket(path: &Path) -> std::io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => match UnixStream::connect(path) {
+            Ok(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("A server is already running on {}", path.display()),
+            )),
+            Err(error) if error.kind() == std::io::ErrorKind::ConnectionRefused => std::fs::remove_file(path),
+            Err(error) => Err(error),
+        },
+        _ => Ok(()),
+    }
+}
+
+// Removes the socket file when the server stops, including when it stops with an error. The file is only
+// removed if it is still the one this server bound, so a socket that has since replaced it is left alone.
+struct SocketFile {
+    path: PathBuf,
+    // (device, inode) of the socket file at bind time
+    id: (u64, u64),
+}
+impl SocketFile {
+    fn new(path: PathBuf) -> std::io::Result<SocketFile> {
+        let id = file_id(&path)?;
+        Ok(SocketFile { path, id })
+    }
+}
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        if file_id(&self.path).is_ok_and(|id| id == self.id) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+fn file_id(path: &Path) -> std::io::Result<(u64, u64)> {
+    let metadata = std::fs::symlink_metadata(path)?;
+    Ok((metadata.dev(), metadata.ino()))
+}
+
+fn handle_command(fresh: &mut IntervalSet, command: &str) -> Result<String, Box<dyn Error>> {
+    let (name, argument) = command.split_once(' ').unwrap_or((command, ""));
+    let argument = argument.trim();
+    Ok(match name {
+        "check" => {
+            let id = argument.parse()?;
+            String::from(if fresh.contains(id) { "fresh" } else { "spoiled" })
+        }
+        // Number of fresh IDs in the range
+        "count" => fresh.count_within(parse_interval(argument)?).to_string(),
+        "add" => {
+            fresh.insert(parse_interval(argument)?);
+            String::from("ok")
+        }
+        "remove" => {
+            fresh.remove(parse_interval(argument)?);
+            String::from("ok")
+        }
+        "total" => fresh.total_length().to_string(),
+        "ranges" => fresh.len().to_string(),
+        "help" => String::from(HELP),
+        _ => return Err(format!("Unknown command {:?}. {}", name, HELP).into()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_session() {
+        let fresh = Mutex::new(IntervalSet::from_iter([(3, 5), (10, 14), (16, 20), (12, 18)]));
+        let commands =
+            "check 5\ncheck 8\ncount 0-12\n\nadd 6-9\ncheck 8\nremove 4-4\ntotal\nranges\ncheck x\nfly\nquit\ntotal\n";
+        let mut output = Vec::new();
+        assert_eq!(
+            serve(&fresh, commands.as_bytes(), &mut output).unwrap(),
+            SessionEnd::Closed
+        );
+        let replies = String::from_utf8(output).unwrap();
+        let replies = replies.lines().collect::<Vec<_>>();
+        assert_eq!(replies[..8], ["fresh", "spoiled", "6", "ok", "fresh", "ok", "17", "2"]);
+        assert!(replies[8].starts_with("error: "));
+        assert!(replies[9].starts_with("error: Unknown command \"fly\""));
+        assert_eq!(replies.len(), 10);
+        assert_eq!(
+            serve(&fresh, "total\nshutdown\ntotal\n".as_bytes(), &mut Vec::new()).unwrap(),
+            SessionEnd::Shutdown
+        );
+    }
+    #[test]
+    fn test_socket() {
+        let path = std::env::temp_dir().join(format!("day5_test_{}.sock", std::process::id()));
+        // Socket file left behind by an earlier server
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        let server_path = path.clone();
+        let server = std::thread::spawn(move || serve_socket(IntervalSet::from_iter([(3, 5)]), server_path));
+        let connect = || {
+            for _ in 0..100 {
+                if let Ok(stream) = UnixStream::connect(&path) {
+                    return stream;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            panic!("Server didn't start");
+        };
+        let send = |stream: &UnixStream, command: &str| {
+            writeln!(&*stream, "{}", command).unwrap();
+            let mut reply = String::new();
+            BufReader::new(stream).read_line(&mut reply).unwrap();
+            reply.trim_end().to_string()
+        };
+        // The first client stays connected without sending anything while the second one is served
+        let idle = connect();
+        let active = connect();
+        // A second server on the same path fails instead of taking the socket over
+        let error = serve_socket(IntervalSet::new(), &path).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AddrInUse);
+        assert_eq!(send(&active, "check 8"), "spoiled");
+        assert_eq!(send(&active, "add 6-9"), "ok");
+        assert_eq!(send(&idle, "check 8"), "fresh");
+        writeln!(&active, "shutdown").unwrap();
+        server.join().unwrap().unwrap();
+        assert!(!path.exists());
+    }
+    #[test]
+    fn test_socket_file_cleanup() {
+        let path = std::env::temp_dir().join(format!("day5_test_cleanup_{}.sock", std::process::id()));
+        let _first = UnixListener::bind(&path).unwrap();
+        let socket_file = SocketFile::new(path.clone()).unwrap();
+        // Another socket replaces the file before this one is cleaned up
+        std::fs::remove_file(&path).unwrap();
+        let _second = UnixListener::bind(&path).unwrap();
+        drop(socket_file);
+        assert!(path.exists());
+        drop(SocketFile::new(path.clone()).unwrap());
+        assert!(!path.exists());
+    }
+}
@@ -1,103 +1,252 @@
-use std::str::Chars;
+mod operators;
+mod orientation;
+mod writer;
+use operators::{Number, OperatorRegistry};
+use orientation::{Orientation, PART_ONE, PART_TWO, get_operands, parse_orientation};
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Precision {
+    // Checked i64 arithmetic, overflow is reported as an error
+    I64,
+    // Checked i128 arithmetic, for worksheets with large products
+    I128,
+}
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Parsing {
+    // Operands that aren't numbers count as 0
+    Lenient,
+    // Operands that aren't numbers are an error. Blank cells are skipped either way.
+    Strict,
+}
 fn main() {
+    let mut precision = Precision::I64;
+    let mut parsing = Parsing::Lenient;
+    let mut orientations = Vec::new();
+    let mut show_operands = false;
+    let mut render_orientation = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--i128" => precision = Precision::I128,
+            "--strict" => parsing = Parsing::Strict,
+            // Total for another reading of the worksheet instead of parts 1 and 2, can be given more than once
+            "--orientation" => match parse_orientation(&args.next().unwrap_or_default()) {
+                Ok(orientation) => orientations.push(orientation),
+                Err(error) => {
+                    println!("{}", error);
+                    return;
+                }
+            },
+            // List each problem's operands for every orientation
+            "--operands" => show_operands = true,
+            // Print the problems laid out again in another orientation, read using the first --orientation
+            "--render" => match parse_orientation(&args.next().unwrap_or_default()) {
+                Ok(orientation) => render_orientation = Some(orientation),
+                Err(error) => {
+                    println!("{}", error);
+                    return;
+                }
+            },
+            _ => {
+                println!("Unknown argument: {}", arg);
+                return;
+            }
+        }
+    }
     let (operand_lines, operators, problem_ranges) = match parse_input("input") {
         Err(error) => {
-            println!("Error occured reading day 6 input: {}", error.to_string());
+            println!("Error occured reading day 6 input: {}", error);
             return;
         }
         Ok(input_data) => input_data,
     };
-    let (part1, part2) = calculate_answers(operand_lines, operators, problem_ranges);
-    println!("\tDay 6\nPart 1: {}\nPart 2: {}", part1, part2);
+    if let Some(render_orientation) = render_orientation {
+        let worksheet = (operand_lines, operators, problem_ranges);
+        let reading = orientations.first().copied().unwrap_or(PART_ONE);
+        let rendered = match precision {
+            Precision::I64 => writer::read_problems::<i64>(&worksheet, reading, parsing)
+                .and_then(|problems| writer::render_worksheet(&problems, render_orientation)),
+            Precision::I128 => writer::read_problems::<i128>(&worksheet, reading, parsing)
+                .and_then(|problems| writer::render_worksheet(&problems, render_orientation)),
+        };
+        match rendered {
+            Err(error) => println!("Error occured rendering day 6: {}", error),
+            Ok(text) => print!("{}", text),
+        }
+        return;
+    }
+    if orientations.is_empty() && !show_operands {
+        match precision {
+            Precision::I64 => print_answers::<i64>(operand_lines, operators, problem_ranges, parsing),
+            Precision::I128 => print_answers::<i128>(operand_lines, operators, problem_ranges, parsing),
+        }
+        return;
+    }
+    if orientations.is_empty() {
+        orientations = vec![PART_ONE, PART_TWO];
+    }
+    let worksheet = (operand_lines, operators, problem_ranges);
+    let result = match precision {
+        Precision::I64 => print_orientations::<i64>(&worksheet, &orientations, parsing, show_operands),
+        Precision::I128 => print_orientations::<i128>(&worksheet, &orientations, parsing, show_operands),
+    };
+    if let Err(error) = result {
+        println!("Error occured solving day 6: {}", error);
+    }
+}
+fn print_orientations<T: Number>(
+    (operand_lines, operators, ranges): &Worksheet,
+    orientations: &[Orientation],
+    parsing: Parsing,
+    show_operands: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let registry = OperatorRegistry::<T>::standard();
+    for &orientation in orientations {
+        println!("Reading {}", orientation);
+        if show_operands {
+            for (operator, range) in std::iter::zip(operators.iter(), ranges.iter()) {
+                let operands = get_operands::<T>(range, operand_lines, orientation, parsing)?;
+                let answer = registry.evaluate(*operator, operands.iter().copied(), range.start + 1)?;
+                let operands = operands.iter().map(T::to_string).collect::<Vec<_>>();
+                println!(
+                    "  Columns {}-{}: {} = {}",
+                    range.start + 1,
+                    range.end,
+                    operands.join(&format!(" {} ", operator)),
+                    answer
+                );
+            }
+        }
+        let total = calculate_total(operand_lines, operators, ranges, orientation, &registry, parsing)?;
+        println!("Total: {}", total);
+    }
+    Ok(())
 }
-fn calculate_answers(
+fn print_answers<T: Number>(
     operand_lines: Vec<Vec<char>>,
     operators: Vec<char>,
     ranges: Vec<std::ops::Range<usize>>,
-) -> (i64, i64) {
-    let mut part1 = 0;
-    let mut part2 = 0;
-    // Part 1
-    for (operator, problem_range) in std::iter::zip(operators.iter(), ranges.iter()) {
-        let part_one_operands = get_part_one_operands(problem_range, &operand_lines);
-        let part_two_operands = get_part_two_operands(problem_range, &operand_lines);
-        let part_one_answer = match operator {
-            '+' => part_one_operands.fold(0_i64, |lhs, rhs| lhs + rhs),
-            '*' => part_one_operands.fold(1_i64, |lhs, rhs| lhs * rhs),
-            _ => 0,
-        };
-        let part_two_answer = match operator {
-            '+' => part_two_operands.into_iter().fold(0_i64, |lhs, rhs| lhs + rhs),
-            '*' => part_two_operands.into_iter().fold(1_i64, |lhs, rhs| lhs * rhs),
-            _ => 0,
-        };
-        part2 += part_two_answer;
-        part1 += part_one_answer;
+    parsing: Parsing,
+) {
+    let registry = OperatorRegistry::<T>::standard();
+    match calculate_answers(operand_lines, operators, ranges, &registry, parsing) {
+        Err(error) => println!("Error occured solving day 6: {}", error),
+        Ok((part1, part2)) => println!("\tDay 6\nPart 1: {}\nPart 2: {}", part1, part2),
     }
-    // Part 2
-    (part1, part2)
 }
-fn get_part_one_operands<'a>(
-    range: &'a std::ops::Range<usize>,
-    operand_lines: &'a Vec<Vec<char>>,
-) -> Box<dyn Iterator<Item = i64> + 'a> {
-    // Need String to stay alive long enough to use &str
-    Box::new(
-        operand_lines
-            .iter()
-            .map(|x| x[range.clone()].into_iter().collect::<String>())
-            .map(parse_operand_string),
-    )
+fn calculate_answers<T: Number>(
+    operand_lines: Vec<Vec<char>>,
+    operators: Vec<char>,
+    ranges: Vec<std::ops::Range<usize>>,
+    registry: &OperatorRegistry<T>,
+    parsing: Parsing,
+) -> Result<(T, T), Box<dyn std::error::Error>> {
+    let part1 = calculate_total(&operand_lines, &operators, &ranges, PART_ONE, registry, parsing)?;
+    let part2 = calculate_total(&operand_lines, &operators, &ranges, PART_TWO, registry, parsing)?;
+    Ok((part1, part2))
+}
+// Sum of every problem's answer when the operands are read in the given orientation
+fn calculate_total<T: Number>(
+    operand_lines: &[Vec<char>],
+    operators: &[char],
+    ranges: &[std::ops::Range<usize>],
+    orientation: Orientation,
+    registry: &OperatorRegistry<T>,
+    parsing: Parsing,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let mut total = T::default();
+    for (operator, problem_range) in std::iter::zip(operators.iter(), ranges.iter()) {
+        // Columns are 1-based in error messages
+        let column = problem_range.start + 1;
+        let operands = get_operands(problem_range, operand_lines, orientation, parsing)?;
+        let answer = registry.evaluate(*operator, operands, column)?;
+        total = total
+            .checked_add(answer)
+            .ok_or_else(|| format!("Grand total overflowed reading {}", orientation))?;
+    }
+    Ok(total)
 }
-fn get_part_two_operands(
-    range: &std::ops::Range<usize>,
-    operand_lines: &Vec<Vec<char>>,
-) -> Box<dyn Iterator<Item = i64>> {
-    let mut operands = Vec::new();
-    // Unlikely to be more than this, might be less.
-    operands.reserve(4);
-    for idx in range.clone() {
-        operands.push(parse_operand_string(
-            operand_lines.iter().map(|x| x[idx]).collect::<String>(),
-        ));
-    }
-    Box::new(operands.into_iter())
+// None for a blank cell. `location` describes where the operand is, for error messages.
+fn parse_operand_string<T, F>(operand_string: &str, parsing: Parsing, location: F) -> Result<Option<T>, String>
+where
+    T: Number,
+    F: Fn() -> String,
+{
+    let trimmed = operand_string.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+    match (trimmed.parse::<T>(), parsing) {
+        (Ok(operand), _) => Ok(Some(operand)),
+        (Err(_), Parsing::Lenient) => Ok(Some(T::default())),
+        (Err(_), Parsing::Strict) => Err(format!("Invalid operand {:?} at {}", operand_string, location())),
+    }
 }
-fn parse_operand_string(operand_string: String) -> i64 { operand_string.as_str().trim().parse::<i64>().unwrap_or(0) }
-fn parse_input<P>(
-    filename: P,
-) -> Result<(Vec<Vec<char>>, Vec<char>, Vec<std::ops::Range<usize>>), Box<dyn std::error::Error>>
+// Operand lines, the operator for each problem, and the columns each problem covers
+type Worksheet = (Vec<Vec<char>>, Vec<char>, Vec<std::ops::Range<usize>>);
+fn parse_input<P>(filename: P) -> Result<Worksheet, Box<dyn std::error::Error>>
 where
     P: AsRef<std::path::Path>,
 {
-    let file_data = std::fs::read_to_string(filename)?;
-    let mut line_vec = file_data.lines().filter(|x| x.len() > 0).collect::<Vec<&str>>();
-    let last_line = line_vec.pop().ok_or("Input file should not be empty")?;
-    line_vec
+    parse_worksheet(&std::fs::read_to_string(filename)?)
+}
+// Short lines are padded with spaces, so trailing whitespace doesn't matter
+fn parse_worksheet(text: &str) -> Result<Worksheet, Box<dyn std::error::Error>> {
+    let mut char_line_vec = text
+        .lines()
+        .filter(|x| !x.is_empty())
+        .map(|line| line.chars().collect::<Vec<char>>())
+        .collect::<Vec<_>>();
+    let width = char_line_vec
         .iter()
-        .all(|line| line.len() == last_line.len())
-        .then(|| ())
-        .ok_or("Input lines are not matching length")?; // Make sure all lines are the same length
-    let (problem_regions, operators) = parse_operator_line(last_line);
-    let char_line_vec = line_vec.into_iter().map(str::chars).map(Chars::collect).collect();
+        .map(Vec::len)
+        .max()
+        .ok_or("Input file should not be empty")?;
+    char_line_vec.iter_mut().for_each(|line| line.resize(width, ' '));
+    let operator_line = char_line_vec.pop().ok_or("Input file should not be empty")?;
+    let problem_regions = find_problem_regions(&char_line_vec, &operator_line);
+    let operators = problem_regions
+        .iter()
+        .map(|region| parse_operator(&operator_line, region))
+        .collect::<Result<Vec<char>, String>>()?;
     Ok((char_line_vec, operators, problem_regions))
 }
-fn parse_operator_line(operator_line: &str) -> (Vec<std::ops::Range<usize>>, Vec<char>) {
-    // Assumes the line is non empty
+// Problems are separated by columns that are blank in every line, including the operator line
+fn find_problem_regions(operand_lines: &[Vec<char>], operator_line: &[char]) -> Vec<std::ops::Range<usize>> {
+    let is_separator = |idx: usize| operator_line[idx] == ' ' && operand_lines.iter().all(|line| line[idx] == ' ');
     let mut problem_regions = Vec::new();
-    let mut operators = Vec::new();
-    let mut last_operator_idx = 0;
-    operators.push(operator_line.chars().next().unwrap());
-    for (idx, ch) in operator_line.chars().enumerate().skip(1) {
-        if ch != ' ' {
-            operators.push(ch);
-            problem_regions.push(last_operator_idx..(idx - 1));
-            last_operator_idx = idx;
-        };
+    let mut region_start = None;
+    for idx in 0..operator_line.len() {
+        match (region_start, is_separator(idx)) {
+            (None, false) => region_start = Some(idx),
+            (Some(start), true) => {
+                problem_regions.push(start..idx);
+                region_start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(start) = region_start {
+        problem_regions.push(start..operator_line.len());
+    }
+    problem_regions
+}
+// The operator can be anywhere under its problem, but there must be exactly one
+fn parse_operator(operator_line: &[char], region: &std::ops::Range<usize>) -> Result<char, String> {
+    let mut operators = operator_line[region.clone()].iter().filter(|&&ch| ch != ' ');
+    match (operators.next(), operators.next()) {
+        (Some(&operator), None) => Ok(operator),
+        (None, _) => Err(format!(
+            "Problem in columns {}-{} has no operator",
+            region.start + 1,
+            region.end
+        )),
+        (Some(_), Some(_)) => Err(format!(
+            "Problem in columns {}-{} has more than one operator",
+            region.start + 1,
+            region.end
+        )),
     }
-    problem_regions.push(last_operator_idx..operator_line.len());
-    (problem_regions, operators)
 }
 
 #[cfg(test)]
@@ -118,15 +267,111 @@ mod tests {
         assert_eq!(regions, vec![0..3, 4..7, 8..11, 12..15]);
     }
     #[test]
+    fn test_precision() {
+        let lines = vec!["99999999999 1".chars().collect(), "99999999999 2".chars().collect()];
+        let (operators, ranges) = (vec!['*', '+'], vec![0..11, 12..13]);
+        let result = calculate_answers(
+            lines.clone(),
+            operators.clone(),
+            ranges.clone(),
+            &OperatorRegistry::<i64>::standard(),
+            Parsing::Strict,
+        );
+        assert!(result.is_err());
+        let (part1, _part2) = calculate_answers(
+            lines,
+            operators,
+            ranges,
+            &OperatorRegistry::<i128>::standard(),
+            Parsing::Strict,
+        )
+        .unwrap();
+        assert_eq!(part1, 9999999999800000000004);
+        let lines = vec![format!("{} {}", i64::MAX, i64::MAX).chars().collect()];
+        let result = calculate_answers(
+            lines,
+            vec!['+', '+'],
+            vec![0..19, 20..39],
+            &OperatorRegistry::<i64>::standard(),
+            Parsing::Strict,
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Grand total overflowed reading rows,left-to-right,top-down"
+        );
+    }
+    #[test]
+    fn test_operand_parsing() {
+        let registry = OperatorRegistry::<i64>::standard();
+        let lines = vec![
+            "12 3".chars().collect(),
+            "4x 5".chars().collect(),
+            "   6".chars().collect(),
+        ];
+        let (operators, ranges) = (vec!['*', '*'], vec![0..2, 3..4]);
+        let lenient = calculate_answers(
+            lines.clone(),
+            operators.clone(),
+            ranges.clone(),
+            &registry,
+            Parsing::Lenient,
+        );
+        // Blank cells are skipped rather than zeroing the product, "4x" still counts as 0
+        assert_eq!(lenient.unwrap(), (90, 356));
+        let strict = calculate_answers(lines, operators, ranges, &registry, Parsing::Strict);
+        assert_eq!(
+            strict.unwrap_err().to_string(),
+            "Invalid operand \"4x\" at row 2, columns 1-2"
+        );
+        let lines = vec!["12".chars().collect(), "4?".chars().collect(), " 6".chars().collect()];
+        let strict = get_operands::<i64>(&(0..2), &lines, PART_TWO, Parsing::Strict);
+        assert_eq!(strict.unwrap_err(), "Invalid operand \"2?6\" at rows 1-3, column 2");
+    }
+    #[test]
+    fn test_ragged_lines() {
+        // Trailing spaces stripped, a wider gap between problems, and operators not at the left of their problem
+        let text = "123 328   51 64\r\n 45 64   387 23\r\n  6 98   215 314\r\n *   +   *   +\r\n";
+        let (lines, operators, regions) = parse_worksheet(text).unwrap();
+        assert_eq!(operators, vec!['*', '+', '*', '+']);
+        assert_eq!(regions, vec![0..3, 4..7, 9..12, 13..16]);
+        assert!(lines.iter().all(|line| line.len() == 16));
+        let registry = OperatorRegistry::<i64>::standard();
+        let answers = calculate_answers(lines, operators, regions, &registry, Parsing::Strict).unwrap();
+        assert_eq!(answers, (4277556, 3263827));
+        assert_eq!(
+            parse_worksheet("12 34\n+\n").unwrap_err().to_string(),
+            "Problem in columns 4-5 has no operator"
+        );
+        assert_eq!(
+            parse_worksheet("1234\n+  *\n").unwrap_err().to_string(),
+            "Problem in columns 1-4 has more than one operator"
+        );
+        assert!(parse_worksheet("\n\n").is_err());
+    }
+    #[test]
     fn test_part_one() {
         let (lines, operators, regions) = parse_input("test").expect("Input file should be at './input'");
-        let (part1, _part2) = calculate_answers(lines, operators, regions);
+        let (part1, _part2) = calculate_answers(
+            lines,
+            operators,
+            regions,
+            &OperatorRegistry::<i64>::standard(),
+            Parsing::Strict,
+        )
+        .unwrap();
         assert_eq!(part1, 4277556);
     }
     #[test]
     fn test_part_two() {
         let (lines, operators, regions) = parse_input("test").expect("Input file should be at './input'");
-        let (_part1, part2) = calculate_answers(lines, operators, regions);
+        let (_part1, part2) = calculate_answers(
+            lines,
+            operators,
+            regions,
+            &OperatorRegistry::<i64>::standard(),
+            Parsing::Strict,
+        )
+        .unwrap();
         assert_eq!(part2, 3263827);
     }
 }
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+// Integer type the worksheet is evaluated in. All arithmetic is checked, so overflow is an error rather than
+// wrapping or panicking.
+pub trait Number: Copy + Ord + Default + Display + FromStr + 'static {
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    fn checked_div(self, rhs: Self) -> Option<Self>;
+    fn checked_rem(self, rhs: Self) -> Option<Self>;
+    fn checked_pow(self, exponent: Self) -> Option<Self>;
+}
+macro_rules! impl_number {
+    ($($t:ty),*) => {
+        $(impl Number for $t {
+            fn checked_add(self, rhs: Self) -> Option<Self> { <$t>::checked_add(self, rhs) }
+            fn checked_sub(self, rhs: Self) -> Option<Self> { <$t>::checked_sub(self, rhs) }
+            fn checked_mul(self, rhs: Self) -> Option<Self> { <$t>::checked_mul(self, rhs) }
+            fn checked_div(self, rhs: Self) -> Option<Self> { <$t>::checked_div(self, rhs) }
+            fn checked_rem(self, rhs: Self) -> Option<Self> { <$t>::checked_rem(self, rhs) }
+            fn checked_pow(self, exponent: Self) -> Option<Self> {
+                <$t>::checked_pow(self, u32::try_from(exponent).ok()?)
+            }
+        })*
+    };
+}
+impl_number!(i64, i128);
+
+// Combines the running result with the next operand. None if the result is undefined or doesn't fit.
+pub type Reducer<T> = Box<dyn Fn(T, T) -> Option<T>>;
+
+// Operators fold their operands from the first one onwards, so "-" is the first operand minus all the others
+// and "^" is ((a ^ b) ^ c).
+pub struct OperatorRegistry<T> {
+    reducers: HashMap<char, Reducer<T>>,
+}
+impl<T: Number> OperatorRegistry<T> {
+    pub fn new() -> OperatorRegistry<T> {
+        OperatorRegistry {
+            reducers: HashMap::new(),
+        }
+    }
+    // + - * / % ^, with '<' for the minimum and '>' for the maximum
+    pub fn standard() -> OperatorRegistry<T> {
+        let mut registry = OperatorRegistry::new();
+        registry.register('+', T::checked_add);
+        registry.register('-', T::checked_sub);
+        registry.register('*', T::checked_mul);
+        registry.register('/', T::checked_div);
+        registry.register('%', T::checked_rem);
+        registry.register('^', T::checked_pow);
+        registry.register('<', |lhs: T, rhs| Some(lhs.min(rhs)));
+        registry.register('>', |lhs: T, rhs| Some(lhs.max(rhs)));
+        registry
+    }
+    // Replaces any existing operator with the same symbol
+    pub fn register<F>(&mut self, symbol: char, reducer: F)
+    where
+        F: Fn(T, T) -> Option<T> + 'static,
+    {
+        self.reducers.insert(symbol, Box::new(reducer));
+    }
+    // Column is only used to say where the problem is in error messages
+    pub fn evaluate<I>(&self, symbol: char, operands: I, column: usize) -> Result<T, String>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let reducer = self
+            .reducers
+            .get(&symbol)
+            .ok_or_else(|| format!("Unknown operator {:?} in column {}", symbol, column))?;
+        let mut operands = operands.into_iter();
+        let first = operands
+            .next()
+            .ok_or_else(|| format!("Operator {:?} in column {} has no operands", symbol, column))?;
+        operands.try_fold(first, |lhs, rhs| {
+            reducer(lhs, rhs).ok_or_else(|| {
+                format!(
+                    "Operator {:?} in column {} overflowed or is undefined for {} and {}",
+                    symbol, column, lhs, rhs
+                )
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_operators() {
+        let mut registry = OperatorRegistry::<i64>::standard();
+        assert_eq!(registry.evaluate('+', [1, 2, 3], 1), Ok(6));
+        assert_eq!(registry.evaluate('-', [10, 2, 3], 1), Ok(5));
+        assert_eq!(registry.evaluate('*', [2, 3, 4], 1), Ok(24));
+        assert_eq!(registry.evaluate('/', [100, 5, 2], 1), Ok(10));
+        assert_eq!(registry.evaluate('%', [100, 7], 1), Ok(2));
+        assert_eq!(registry.evaluate('^', [2, 3, 2], 1), Ok(64));
+        assert_eq!(registry.evaluate('<', [5, -3, 8], 1), Ok(-3));
+        assert_eq!(registry.evaluate('>', [5, -3, 8], 1), Ok(8));
+        assert_eq!(registry.evaluate('+', [7], 1), Ok(7));
+        assert_eq!(
+            registry.evaluate('?', [1, 2], 13),
+            Err(String::from("Unknown operator '?' in column 13"))
+        );
+        assert_eq!(
+            registry.evaluate('/', [1, 0], 5),
+            Err(String::from(
+                "Operator '/' in column 5 overflowed or is undefined for 1 and 0"
+            ))
+        );
+        assert!(registry.evaluate('^', [2, -1], 1).is_err());
+        assert!(registry.evaluate('*', [i64::MAX, 2], 1).is_err());
+        assert!(registry.evaluate('+', [], 1).is_err());
+        registry.register('&', |lhs, rhs| Some(lhs & rhs));
+        assert_eq!(registry.evaluate('&', [0b1110, 0b0111], 1), Ok(0b0110));
+    }
+    #[test]
+    fn test_wide_operators() {
+        let narrow = OperatorRegistry::<i64>::standard();
+        let wide = OperatorRegistry::<i128>::standard();
+        let operands = [4_000_000_000, 4_000_000_000, 4_000_000_000];
+        assert!(narrow.evaluate('*', operands, 1).is_err());
+        assert_eq!(
+            wide.evaluate('*', operands.map(i128::from), 1),
+            Ok(64_000_000_000_000_000_000_000_000_000)
+        );
+        assert!(wide.evaluate('^', [10, 39], 1).is_err());
+    }
+}
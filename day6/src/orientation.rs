@@ -0,0 +1,134 @@
+use std::fmt;
+
+use crate::operators::Number;
+use crate::{Parsing, parse_operand_string};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Axis {
+    // Each row of a problem is one operand
+    Rows,
+    // Each column of a problem is one operand
+    Columns,
+}
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Horizontal {
+    LeftToRight,
+    RightToLeft,
+}
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Vertical {
+    TopDown,
+    BottomUp,
+}
+// How to read the operands of a problem. For rows, `vertical` is the order of the operands and `horizontal` the
+// order of the digits within each one. For columns it's the other way round.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orientation {
+    pub axis: Axis,
+    pub horizontal: Horizontal,
+    pub vertical: Vertical,
+}
+pub const PART_ONE: Orientation = Orientation {
+    axis: Axis::Rows,
+    horizontal: Horizontal::LeftToRight,
+    vertical: Vertical::TopDown,
+};
+pub const PART_TWO: Orientation = Orientation {
+    axis: Axis::Columns,
+    horizontal: Horizontal::LeftToRight,
+    vertical: Vertical::TopDown,
+};
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let axis = match self.axis {
+            Axis::Rows => "rows",
+            Axis::Columns => "columns",
+        };
+        let horizontal = match self.horizontal {
+            Horizontal::LeftToRight => "left-to-right",
+            Horizontal::RightToLeft => "right-to-left",
+        };
+        let vertical = match self.vertical {
+            Vertical::TopDown => "top-down",
+            Vertical::BottomUp => "bottom-up",
+        };
+        write!(f, "{},{},{}", axis, horizontal, vertical)
+    }
+}
+
+// Comma separated, e.g. "columns,right-to-left" or "rows,bottom-up". Anything left out is the same as part 1.
+pub fn parse_orientation(spec: &str) -> Result<Orientation, String> {
+    let mut orientation = PART_ONE;
+    for part in spec.split(',').map(str::trim) {
+        match part {
+            "rows" => orientation.axis = Axis::Rows,
+            "columns" => orientation.axis = Axis::Columns,
+            "left-to-right" => orientation.horizontal = Horizontal::LeftToRight,
+            "right-to-left" => orientation.horizontal = Horizontal::RightToLeft,
+            "top-down" => orientation.vertical = Vertical::TopDown,
+            "bottom-up" => orientation.vertical = Vertical::BottomUp,
+            _ => return Err(format!("Unknown orientation {:?}", part)),
+        }
+    }
+    Ok(orientation)
+}
+
+pub fn get_operands<T: Number>(
+    range: &std::ops::Range<usize>,
+    operand_lines: &[Vec<char>],
+    orientation: Orientation,
+    parsing: Parsing,
+) -> Result<Vec<T>, String> {
+    let mut rows = (0..operand_lines.len()).collect::<Vec<_>>();
+    if orientation.vertical == Vertical::BottomUp {
+        rows.reverse();
+    }
+    let mut columns = range.clone().collect::<Vec<_>>();
+    if orientation.horizontal == Horizontal::RightToLeft {
+        columns.reverse();
+    }
+    let mut operands = Vec::new();
+    match orientation.axis {
+        Axis::Rows => {
+            for &row in rows.iter() {
+                let operand_string = columns.iter().map(|&col| operand_lines[row][col]).collect::<String>();
+                let location = || format!("row {}, columns {}-{}", row + 1, range.start + 1, range.end);
+                operands.extend(parse_operand_string::<T, _>(&operand_string, parsing, location)?);
+            }
+        }
+        Axis::Columns => {
+            for &col in columns.iter() {
+                let operand_string = rows.iter().map(|&row| operand_lines[row][col]).collect::<String>();
+                let location = || format!("rows 1-{}, column {}", operand_lines.len(), col + 1);
+                operands.extend(parse_operand_string::<T, _>(&operand_string, parsing, location)?);
+            }
+        }
+    }
+    Ok(operands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_orientations() {
+        let lines = vec![
+            "123".chars().collect(),
+            " 45".chars().collect(),
+            "  6".chars().collect(),
+        ];
+        let read = |spec: &str| get_operands::<i64>(&(0..3), &lines, parse_orientation(spec).unwrap(), Parsing::Strict);
+        assert_eq!(read("rows"), Ok(vec![123, 45, 6]));
+        assert_eq!(read("rows,right-to-left"), Ok(vec![321, 54, 6]));
+        assert_eq!(read("rows,bottom-up"), Ok(vec![6, 45, 123]));
+        assert_eq!(read("columns"), Ok(vec![1, 24, 356]));
+        assert_eq!(read("columns,right-to-left"), Ok(vec![356, 24, 1]));
+        assert_eq!(read("columns,bottom-up,right-to-left"), Ok(vec![653, 42, 1]));
+        assert_eq!(
+            parse_orientation("diagonal"),
+            Err(String::from("Unknown orientation \"diagonal\""))
+        );
+        assert_eq!(PART_TWO.to_string(), "columns,left-to-right,top-down");
+        assert_eq!(parse_orientation(&PART_TWO.to_string()), Ok(PART_TWO));
+    }
+}
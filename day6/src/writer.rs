@@ -0,0 +1,160 @@
+use crate::operators::Number;
+use crate::orientation::{Axis, Horizontal, Orientation, Vertical, get_operands};
+use crate::{Parsing, Worksheet};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Problem<T> {
+    pub operator: char,
+    pub operands: Vec<T>,
+}
+
+// Operands of every problem on a parsed worksheet, read in the given orientation
+pub fn read_problems<T: Number>(
+    (operand_lines, operators, ranges): &Worksheet,
+    orientation: Orientation,
+    parsing: Parsing,
+) -> Result<Vec<Problem<T>>, String> {
+    std::iter::zip(operators.iter(), ranges.iter())
+        .map(|(&operator, range)| {
+            let operands = get_operands(range, operand_lines, orientation, parsing)?;
+            Ok(Problem { operator, operands })
+        })
+        .collect()
+}
+
+// Lays the problems out so that reading the worksheet in the same orientation gives them back. Problems are
+// separated by a single blank column, with the operator under the first column of its problem.
+pub fn render_worksheet<T: Number>(problems: &[Problem<T>], orientation: Orientation) -> Result<String, String> {
+    for (idx, problem) in problems.iter().enumerate() {
+        if problem.operands.is_empty() {
+            return Err(format!("Problem {} has no operands", idx + 1));
+        }
+        if problem.operator.is_whitespace() {
+            return Err(format!("Problem {} has a blank operator", idx + 1));
+        }
+    }
+    let operand_strings = problems
+        .iter()
+        .map(|problem| problem.operands.iter().map(T::to_string).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    // Rows hold one operand each for rows, or one digit of every operand for columns
+    let height = match orientation.axis {
+        Axis::Rows => operand_strings.iter().map(Vec::len).max(),
+        Axis::Columns => operand_strings.iter().flatten().map(String::len).max(),
+    }
+    .unwrap_or(0);
+    let mut lines = vec![String::new(); height + 1];
+    for (problem, strings) in std::iter::zip(problems.iter(), operand_strings.iter()) {
+        let cells = render_problem(strings, orientation, height);
+        let width = cells.first().map_or(0, Vec::len);
+        for (line, row) in lines.iter_mut().zip(cells.iter()) {
+            line.extend(row.iter());
+            line.push(' ');
+        }
+        lines[height].push(problem.operator);
+        lines[height].extend(std::iter::repeat_n(' ', width));
+    }
+    Ok(lines.iter().map(|line| format!("{}\n", line.trim_end())).collect())
+}
+
+// Cells of one problem, built in reading order then flipped to match the orientation
+fn render_problem(operand_strings: &[String], orientation: Orientation, height: usize) -> Vec<Vec<char>> {
+    let mut cells = match orientation.axis {
+        Axis::Rows => {
+            // Right aligned, so the widest operand covers every column
+            let width = operand_strings.iter().map(String::len).max().unwrap_or(0);
+            let mut cells = vec![vec![' '; width]; height];
+            for (row, operand) in cells.iter_mut().zip(operand_strings.iter()) {
+                row[width - operand.len()..]
+                    .iter_mut()
+                    .zip(operand.chars())
+                    .for_each(|(cell, ch)| *cell = ch);
+            }
+            cells
+        }
+        Axis::Columns => {
+            // Top aligned, one column per operand
+            let mut cells = vec![vec![' '; operand_strings.len()]; height];
+            for (col, operand) in operand_strings.iter().enumerate() {
+                cells
+                    .iter_mut()
+                    .zip(operand.chars())
+                    .for_each(|(row, ch)| row[col] = ch);
+            }
+            cells
+        }
+    };
+    if orientation.vertical == Vertical::BottomUp {
+        cells.reverse();
+    }
+    if orientation.horizontal == Horizontal::RightToLeft {
+        cells.iter_mut().for_each(|row| row.reverse());
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orientation::{PART_ONE, PART_TWO, parse_orientation};
+    use crate::parse_worksheet;
+    use shared::Lcg;
+    #[test]
+    fn test_render() {
+        let problems = vec![
+            Problem {
+                operator: '*',
+                operands: vec![123_i64, 45, 6],
+            },
+            Problem {
+                operator: '+',
+                operands: vec![10, 2],
+            },
+        ];
+        assert_eq!(
+            render_worksheet(&problems, PART_ONE).unwrap(),
+            "123 10\n 45  2\n  6\n*   +\n"
+        );
+        assert_eq!(
+            render_worksheet(&problems, PART_TWO).unwrap(),
+            "146 12\n25  0\n3\n*   +\n"
+        );
+        let empty = vec![Problem::<i64> {
+            operator: '+',
+            operands: vec![],
+        }];
+        assert_eq!(
+            render_worksheet(&empty, PART_ONE),
+            Err(String::from("Problem 1 has no operands"))
+        );
+    }
+    #[test]
+    fn test_round_trip() {
+        // Seeded so the test is repeatable
+        let mut rng = Lcg::new(6);
+        let mut next = |limit: u64| rng.next_below(limit);
+        let operators = ['+', '*', '-', '^', '<'];
+        let problems = (0..50)
+            .map(|_| Problem {
+                operator: operators[next(operators.len() as u64) as usize],
+                operands: (0..1 + next(5))
+                    .map(|_| {
+                        let num_digits = 1 + next(6) as u32;
+                        next(10_u64.pow(num_digits)) as i64 - 50
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        for axis in ["rows", "columns"] {
+            for horizontal in ["left-to-right", "right-to-left"] {
+                for vertical in ["top-down", "bottom-up"] {
+                    let orientation = parse_orientation(&format!("{},{},{}", axis, horizontal, vertical)).unwrap();
+                    let text = render_worksheet(&problems, orientation).unwrap();
+                    let worksheet = parse_worksheet(&text).unwrap();
+                    let parsed = read_problems::<i64>(&worksheet, orientation, Parsing::Strict).unwrap();
+                    assert_eq!(parsed, problems, "{}\n{}", orientation, text);
+                }
+            }
+        }
+    }
+}
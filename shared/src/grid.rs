@@ -0,0 +1,238 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+// Offsets (row, col) for the 4 orthogonal neighbours, and for all 8 surrounding cells
+const VON_NEUMANN_OFFSETS: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const MOORE_OFFSETS: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+// Row-major 2D grid, cells are addressed as (row, col)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Result<Grid<T>, Box<dyn Error>> {
+        let height = rows.len();
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some(row_idx) = rows.iter().position(|row| row.len() != width) {
+            return Err(format!(
+                "Row {} has length {}, expected {}",
+                row_idx + 1,
+                rows[row_idx].len(),
+                width
+            )
+            .into());
+        }
+        Ok(Grid {
+            width,
+            height,
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+    // Parses one cell per character, with one row per line. Rows are numbered by line in errors, so blank
+    // lines are only allowed at the end of the text.
+    pub fn from_char_map<F>(text: &str, mut parse_cell: F) -> Result<Grid<T>, Box<dyn Error>>
+    where
+        F: FnMut(char) -> Option<T>,
+    {
+        let mut rows = Vec::new();
+        for (row_idx, line) in text.trim_end_matches(['\n', '\r']).lines().enumerate() {
+            if line.is_empty() {
+                return Err(format!("Row {} is blank", row_idx + 1).into());
+            }
+            let mut row = Vec::with_capacity(line.len());
+            for (col_idx, character) in line.chars().enumerate() {
+                let cell = parse_cell(character).ok_or_else(|| {
+                    format!(
+                        "Unexpected character {:?} at row {}, column {}",
+                        character,
+                        row_idx + 1,
+                        col_idx + 1
+                    )
+                })?;
+                row.push(cell);
+            }
+            rows.push(row);
+        }
+        Grid::from_rows(rows)
+    }
+    pub fn to_char_map<F>(&self, mut cell_char: F) -> String
+    where
+        F: FnMut(&T) -> char,
+    {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+        for row in self.rows() {
+            text.extend(row.iter().map(&mut cell_char));
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn width(&self) -> usize { self.width }
+    pub fn height(&self) -> usize { self.height }
+    pub fn in_bounds(&self, row: isize, col: isize) -> bool {
+        row >= 0 && col >= 0 && (row as usize) < self.height && (col as usize) < self.width
+    }
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        (row < self.height && col < self.width).then(|| &self.cells[row * self.width + col])
+    }
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        (row < self.height && col < self.width).then(|| &mut self.cells[row * self.width + col])
+    }
+    // Signed access, anything outside the grid reads as `padding`
+    pub fn get_or<'a>(&'a self, row: isize, col: isize, padding: &'a T) -> &'a T {
+        match self.in_bounds(row, col) {
+            true => &self.cells[row as usize * self.width + col as usize],
+            false => padding,
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &[T] { &self.cells[row * self.width..(row + 1) * self.width] }
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> { self.cells.chunks_exact(self.width.max(1)) }
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        assert!(col < self.width, "Column {} is outside the grid", col);
+        self.cells.iter().skip(col).step_by(self.width)
+    }
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|col| self.column(col))
+    }
+    // All cells in row-major order, with their positions
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(idx, cell)| ((idx / self.width, idx % self.width), cell))
+    }
+
+    // Positions of the in-bounds orthogonal neighbours of (row, col)
+    pub fn neighbours4(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offset_positions(row, col, &VON_NEUMANN_OFFSETS)
+    }
+    // Positions of the in-bounds cells surrounding (row, col), including diagonals
+    pub fn neighbours8(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.offset_positions(row, col, &MOORE_OFFSETS)
+    }
+    fn offset_positions<'a>(
+        &'a self,
+        row: usize,
+        col: usize,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = (usize, usize)> + 'a {
+        offsets
+            .iter()
+            .map(move |(row_offset, col_offset)| (row as isize + row_offset, col as isize + col_offset))
+            .filter(|&(row, col)| self.in_bounds(row, col))
+            .map(|(row, col)| (row as usize, col as usize))
+    }
+
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(
+            row < self.height && col < self.width,
+            "({}, {}) is outside the grid",
+            row,
+            col
+        );
+        &self.cells[row * self.width + col]
+    }
+}
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        assert!(
+            row < self.height && col < self.width,
+            "({}, {}) is outside the grid",
+            row,
+            col
+        );
+        &mut self.cells[row * self.width + col]
+    }
+}
+
+impl Grid<char> {
+    pub fn parse(text: &str) -> Result<Grid<char>, Box<dyn Error>> { Grid::from_char_map(text, Some) }
+}
+impl fmt::Display for Grid<char> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { write!(f, "{}", self.to_char_map(|&x| x)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const MAP: &str = "..@\n@@.\n";
+    #[test]
+    fn test_parse_and_print() {
+        let grid = Grid::from_char_map(MAP, |x| match x {
+            '@' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.row(1), [true, true, false]);
+        assert_eq!(grid.to_char_map(|&x| if x { '@' } else { '.' }), MAP);
+        assert_eq!(Grid::parse(MAP).unwrap().to_string(), MAP);
+
+        let error = Grid::from_char_map("..@\n@x.\n", |x| (x != 'x').then_some(x)).unwrap_err();
+        assert_eq!(error.to_string(), "Unexpected character 'x' at row 2, column 2");
+        assert!(Grid::parse("..@\n@.\n").is_err());
+        assert_eq!(Grid::parse("..@\r\n@@.\r\n\r\n\n").unwrap().to_string(), MAP);
+        assert_eq!(Grid::parse("..@\n\n@@.\n").unwrap_err().to_string(), "Row 2 is blank");
+        let error = Grid::parse("\n..@\n@x.\n").unwrap_err();
+        assert_eq!(error.to_string(), "Row 1 is blank");
+    }
+    #[test]
+    fn test_access() {
+        let mut grid = Grid::parse(MAP).unwrap();
+        assert_eq!(grid.get(0, 2), Some(&'@'));
+        assert_eq!(grid.get(0, 3), None);
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.get_or(-1, 0, &' '), &' ');
+        assert_eq!(grid.get_or(1, 1, &' '), &'@');
+        grid[(0, 0)] = '#';
+        assert_eq!(grid[(0, 0)], '#');
+        assert_eq!(grid.column(2).collect::<String>(), "@.");
+        assert_eq!(
+            grid.columns().map(|x| x.collect::<String>()).collect::<Vec<_>>(),
+            ["#@", ".@", "@."]
+        );
+    }
+    #[test]
+    #[should_panic(expected = "Column 3 is outside the grid")]
+    fn test_column_out_of_bounds() { Grid::parse(MAP).unwrap().column(3).for_each(drop); }
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::new(3, 3, 0);
+        assert_eq!(grid.neighbours4(0, 0).collect::<Vec<_>>(), [(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbours8(0, 0).collect::<Vec<_>>(), [(0, 1), (1, 0), (1, 1)]);
+        assert_eq!(grid.neighbours4(1, 1).count(), 4);
+        assert_eq!(grid.neighbours8(1, 1).count(), 8);
+        assert_eq!(grid.neighbours8(2, 1).count(), 5);
+    }
+}
@@ -0,0 +1,201 @@
+// Set of i64 values stored as sorted, disjoint, non-adjacent inclusive ranges
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    intervals: Vec<(i64, i64)>,
+}
+impl IntervalSet {
+    pub fn new() -> IntervalSet { IntervalSet::default() }
+    // Number of separate ranges, not the number of values
+    pub fn len(&self) -> usize { self.intervals.len() }
+    pub fn is_empty(&self) -> bool { self.intervals.is_empty() }
+    pub fn iter(&self) -> impl Iterator<Item = (i64, i64)> + '_ { self.intervals.iter().copied() }
+    // Ranges with start > end are empty, so inserting or removing them does nothing
+    pub fn insert(&mut self, (start, end): (i64, i64)) {
+        if start > end {
+            return;
+        }
+        // Every range that overlaps or touches the new one gets merged into it
+        let first = self
+            .intervals
+            .partition_point(|interval| interval.1.saturating_add(1) < start);
+        let last = self
+            .intervals
+            .partition_point(|interval| interval.0 <= end.saturating_add(1));
+        let mut merged = (start, end);
+        if first < last {
+            merged.0 = merged.0.min(self.intervals[first].0);
+            merged.1 = merged.1.max(self.intervals[last - 1].1);
+        }
+        self.intervals.splice(first..last, [merged]);
+    }
+    pub fn remove(&mut self, (start, end): (i64, i64)) {
+        if start > end {
+            return;
+        }
+        let first = self.intervals.partition_point(|interval| interval.1 < start);
+        let last = self.intervals.partition_point(|interval| interval.0 <= end);
+        if first == last {
+            return;
+        }
+        // Only the first and last overlapping ranges can stick out past the removed range
+        let mut kept = Vec::with_capacity(2);
+        if self.intervals[first].0 < start {
+            kept.push((self.intervals[first].0, start - 1));
+        }
+        if self.intervals[last - 1].1 > end {
+            kept.push((end + 1, self.intervals[last - 1].1));
+        }
+        self.intervals.splice(first..last, kept);
+    }
+    // Binary search for the first range that doesn't end before the value. O(log n)
+    pub fn contains(&self, value: i64) -> bool {
+        let idx = self.intervals.partition_point(|interval| interval.1 < value);
+        self.intervals.get(idx).is_some_and(|interval| interval.0 <= value)
+    }
+    // Number of values in the set. u128 because the full i64 range holds 2^64 values.
+    pub fn total_length(&self) -> u128 {
+        self.intervals
+            .iter()
+            .map(|&(start, end)| (end as i128 - start as i128 + 1) as u128)
+            .sum()
+    }
+    // Number of values in the set that are also within the inclusive range. O(log n + overlapping ranges)
+    pub fn count_within(&self, (start, end): (i64, i64)) -> u128 {
+        let first = self.intervals.partition_point(|interval| interval.1 < start);
+        let last = self.intervals.partition_point(|interval| interval.0 <= end);
+        self.intervals
+            .get(first..last)
+            .unwrap_or_default()
+            .iter()
+            .map(|&interval| (interval.1.min(end) as i128 - interval.0.max(start) as i128 + 1) as u128)
+            .sum()
+    }
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet { self.iter().chain(other.iter()).collect() }
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::new();
+        let (mut idx, mut other_idx) = (0, 0);
+        while let (Some(&interval), Some(&other_interval)) = (self.intervals.get(idx), other.intervals.get(other_idx)) {
+            let overlap = (interval.0.max(other_interval.0), interval.1.min(other_interval.1));
+            if overlap.0 <= overlap.1 {
+                intervals.push(overlap);
+            }
+            // Move past whichever range ends first, the other one might still overlap the next range
+            if interval.1 < other_interval.1 {
+                idx += 1;
+            } else {
+                other_idx += 1;
+            }
+        }
+        IntervalSet { intervals }
+    }
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        self.intersection(&other.complement((i64::MIN, i64::MAX)))
+    }
+    // Values within the inclusive bounds that are not in the set
+    pub fn complement(&self, bounds: (i64, i64)) -> IntervalSet {
+        let mut intervals = Vec::new();
+        // Start of the next gap, None once the set reaches i64::MAX
+        let mut gap_start = Some(bounds.0);
+        for &(start, end) in self.intervals.iter() {
+            let Some(current) = gap_start else { break };
+            if start > bounds.1 {
+                break;
+            }
+            if start > current {
+                intervals.push((current, start - 1));
+            }
+            if end >= current {
+                gap_start = end.checked_add(1);
+            }
+        }
+        if let Some(current) = gap_start
+            && current <= bounds.1
+        {
+            intervals.push((current, bounds.1));
+        }
+        IntervalSet { intervals }
+    }
+    // Values between the first and last ranges that are missing from the set
+    pub fn gaps(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        self.intervals.windows(2).map(|pair| (pair[0].1 + 1, pair[1].0 - 1))
+    }
+}
+// Sorts then sweeps once, merging ranges that overlap or touch. O(n log n)
+impl FromIterator<(i64, i64)> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = (i64, i64)>>(iter: I) -> IntervalSet {
+        let mut sorted = iter
+            .into_iter()
+            .filter(|interval| interval.0 <= interval.1)
+            .collect::<Vec<_>>();
+        sorted.sort_unstable();
+        let mut intervals: Vec<(i64, i64)> = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            match intervals.last_mut() {
+                Some(last) if interval.0 <= last.1.saturating_add(1) => last.1 = last.1.max(interval.1),
+                _ => intervals.push(interval),
+            }
+        }
+        IntervalSet { intervals }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_insert_and_remove() {
+        let mut set = IntervalSet::new();
+        set.insert((10, 14));
+        set.insert((3, 5));
+        set.insert((16, 20));
+        set.insert((7, 2));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [(3, 5), (10, 14), (16, 20)]);
+        set.insert((15, 15));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [(3, 5), (10, 20)]);
+        set.remove((12, 12));
+        set.remove((0, 3));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [(4, 5), (10, 11), (13, 20)]);
+        set.remove((5, 15));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [(4, 4), (16, 20)]);
+        assert!(set.contains(4) && set.contains(20));
+        assert!(!set.contains(3) && !set.contains(10) && !set.contains(21));
+        assert_eq!(set.total_length(), 6);
+        assert_eq!(set.count_within((0, 17)), 3);
+        assert_eq!(set.count_within((5, 15)), 0);
+        assert_eq!(set.count_within((9, 3)), 0);
+        assert_eq!(set.gaps().collect::<Vec<_>>(), [(5, 15)]);
+        let set = IntervalSet::from_iter([(12, 18), (3, 5), (16, 20), (10, 14), (6, 6)]);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [(3, 6), (10, 20)]);
+    }
+    #[test]
+    fn test_set_algebra() {
+        let a = IntervalSet::from_iter([(0, 10), (20, 30)]);
+        let b = IntervalSet::from_iter([(5, 25), (40, 50)]);
+        assert_eq!(a.union(&b).iter().collect::<Vec<_>>(), [(0, 30), (40, 50)]);
+        assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), [(5, 10), (20, 25)]);
+        assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), [(0, 4), (26, 30)]);
+        assert_eq!(b.difference(&a).iter().collect::<Vec<_>>(), [(11, 19), (40, 50)]);
+        assert_eq!(
+            a.complement((-5, 35)).iter().collect::<Vec<_>>(),
+            [(-5, -1), (11, 19), (31, 35)]
+        );
+        assert_eq!(a.complement((3, 8)), IntervalSet::new());
+        assert_eq!(a.complement((12, 15)).iter().collect::<Vec<_>>(), [(12, 15)]);
+    }
+    #[test]
+    fn test_extremes() {
+        let everything = IntervalSet::from_iter([(i64::MIN, 0), (1, i64::MAX)]);
+        assert_eq!(everything.len(), 1);
+        assert_eq!(everything.total_length(), 1 << 64);
+        assert_eq!(everything.count_within((i64::MIN, i64::MAX)), 1 << 64);
+        assert!(everything.complement((i64::MIN, i64::MAX)).is_empty());
+        let mut set = everything.clone();
+        set.remove((i64::MIN, -1));
+        set.remove((i64::MAX, i64::MAX));
+        assert_eq!(set.iter().collect::<Vec<_>>(), [(0, i64::MAX - 1)]);
+        assert_eq!(
+            set.complement((i64::MIN, i64::MAX)).iter().collect::<Vec<_>>(),
+            [(i64::MIN, -1), (i64::MAX, i64::MAX)]
+        );
+    }
+}
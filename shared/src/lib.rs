@@ -1,3 +1,9 @@
+pub mod grid;
+pub mod interval;
+pub mod random;
+pub use grid::Grid;
+pub use interval::IntervalSet;
+pub use random::Lcg;
 
 use std::fs::File;
 use std::io::{self, BufRead};
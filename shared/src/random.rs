@@ -0,0 +1,36 @@
+// Small deterministic generator for tests and benchmarks, so they are repeatable without any dependencies.
+// This is a plain 64-bit LCG and is nowhere near good enough for anything that needs real randomness.
+#[derive(Clone, Debug)]
+pub struct Lcg {
+    state: u64,
+}
+
+impl Lcg {
+    pub fn new(seed: u64) -> Lcg { Lcg { state: seed } }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self
+            .state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        // The low bits of an LCG repeat quickly, so only the high bits are used
+        self.state >> 33
+    }
+    // Value in 0..limit
+    pub fn next_below(&mut self, limit: u64) -> u64 { self.next_u64() % limit }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_repeatable() {
+        let values = |seed| {
+            let mut rng = Lcg::new(seed);
+            (0..100).map(|_| rng.next_below(10)).collect::<Vec<_>>()
+        };
+        assert_eq!(values(5), values(5));
+        assert_ne!(values(5), values(6));
+        assert!(values(5).iter().all(|&x| x < 10));
+        assert!((0..10).all(|digit| values(5).contains(&digit)));
+    }
+}